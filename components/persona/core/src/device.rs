@@ -1,30 +1,76 @@
-use crate::{util, Error};
+mod log;
+mod webauthn;
+
+pub use log::{ConsistencyProof, DeviceLog, DeviceLogNode, InclusionProof, LogExtension, LogHead};
+pub use webauthn::WebAuthnSignature;
+
+#[cfg(feature = "ml-dsa")]
+use crate::maybestd::boxed::Box;
+use crate::{
+    maybestd::vec::Vec,
+    util::{self, Sha256Digest, Sha256Pipe},
+    Error,
+};
 use borsh::{BorshDeserialize, BorshSerialize};
 use digest::{typenum::U64, Digest};
 use ed25519_dalek::{
     Signature as Ed25519Signature, SigningKey as Ed25519SigningKey,
     VerifyingKey as Ed25519VerifyingKey,
 };
+use k256::ecdsa::{
+    Signature as Secp256k1Signature, SigningKey as Secp256k1SigningKey,
+    VerifyingKey as Secp256k1VerifyingKey,
+};
+#[cfg(feature = "ml-dsa")]
+use ml_dsa::{MlDsa44, SigningKey as MlDsa44SigningKey, VerifyingKey as MlDsa44VerifyingKey};
+use p256::ecdsa::{
+    Signature as P256Signature, SigningKey as P256SigningKey, VerifyingKey as P256VerifyingKey,
+};
 use sha2::Sha512;
-use signature::{DigestSigner, DigestVerifier, Error as SignatureError, Verifier};
+use signature::{
+    hazmat::{PrehashSigner, PrehashVerifier},
+    DigestSigner, Error as SignatureError, Signer, Verifier,
+};
 
-///
-#[derive(Copy, Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+/// A device's public key, and the head of its append-only [`DeviceLog`].
+#[derive(Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Device {
+    #[cfg_attr(feature = "serde", serde(rename = "key"))]
     inner: DeviceInner,
-    // log: MerkleLog<DeviceLogNode>,
+    #[cfg_attr(feature = "serde", serde(rename = "head"))]
+    log: LogHead,
 }
 
 impl Device {
+    /// The device's id, which is its public key for [`Ed25519`] devices, and
+    /// the digest of its encoded public key otherwise.
+    ///
+    /// [`Ed25519`]: Ed25519VerifyingKey
     pub fn id(&self) -> [u8; 32] {
-        match self.inner {
+        match &self.inner {
             DeviceInner::Ed25519(pk) => pk.to_bytes(),
+            _ => Sha256Pipe::encode_to_writer(&self.inner, util::Empty)
+                .map(Sha256Digest::from)
+                .expect("should never fail to serialize and compute digest")
+                .to_bytes(),
         }
     }
+
+    /// The head of the device's log.
+    pub fn head(&self) -> &LogHead {
+        &self.log
+    }
+
+    /// The device's public key, prefixed by its key type's multicodec (as in
+    /// a `Multikey`), with ECDSA keys as compressed SEC1 points.
+    pub fn public_key_multicodec(&self) -> Vec<u8> {
+        self.inner.to_multicodec()
+    }
 }
 
 ///
-#[derive(Copy, Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[derive(Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
 #[borsh(use_discriminant = true)]
 #[non_exhaustive]
 enum DeviceInner {
@@ -35,9 +81,85 @@ enum DeviceInner {
         )]
         Ed25519VerifyingKey,
     ),
+    Secp256k1(
+        #[borsh(
+            deserialize_with = "util::secp256k1::deserialize_key",
+            serialize_with = "util::secp256k1::serialize_key"
+        )]
+        Secp256k1VerifyingKey,
+    ),
+    P256(
+        #[borsh(
+            deserialize_with = "util::p256::deserialize_key",
+            serialize_with = "util::p256::serialize_key"
+        )]
+        P256VerifyingKey,
+    ),
+    /// An encoded ML-DSA-44 key, boxed so as not to inflate every other
+    /// [`Device`].
+    #[cfg(feature = "ml-dsa")]
+    MlDsa44(Box<[u8; util::ml_dsa::KEY_LEN]>),
+}
+
+impl DeviceInner {
+    fn to_multicodec(&self) -> Vec<u8> {
+        let (codec, key): (&[u8], Vec<u8>) = match self {
+            Self::Ed25519(pk) => (&[0xed, 0x01], pk.to_bytes().to_vec()),
+            Self::Secp256k1(pk) => (&[0xe7, 0x01], pk.to_encoded_point(true).as_bytes().to_vec()),
+            Self::P256(pk) => (&[0x80, 0x24], pk.to_encoded_point(true).as_bytes().to_vec()),
+            #[cfg(feature = "ml-dsa")]
+            Self::MlDsa44(pk) => (&[0x90, 0x24], pk.to_vec()),
+        };
+        [codec, &key].concat()
+    }
+
+    #[cfg(feature = "serde")]
+    fn from_multicodec(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0xed, 0x01, key @ ..] => key
+                .try_into()
+                .ok()
+                .and_then(|key| Ed25519VerifyingKey::from_bytes(key).ok())
+                .map(Self::Ed25519),
+            [0xe7, 0x01, key @ ..] => Secp256k1VerifyingKey::from_sec1_bytes(key)
+                .ok()
+                .map(Self::Secp256k1),
+            [0x80, 0x24, key @ ..] => P256VerifyingKey::from_sec1_bytes(key).ok().map(Self::P256),
+            #[cfg(feature = "ml-dsa")]
+            [0x90, 0x24, key @ ..] => <[u8; util::ml_dsa::KEY_LEN]>::try_from(key)
+                .ok()
+                .map(|key| Self::MlDsa44(Box::new(key))),
+            _ => None,
+        }
+    }
+}
+
+/// Serializes the key as its multibase-encoded multicodec, as in a `Multikey`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for DeviceInner {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&multibase::encode(
+            multibase::Base::Base58Btc,
+            self.to_multicodec(),
+        ))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for DeviceInner {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use crate::maybestd::string::String;
+        use serde::de::Error as _;
+
+        let s = String::deserialize(deserializer)?;
+        let (_, bytes) = multibase::decode(s).map_err(D::Error::custom)?;
+        Self::from_multicodec(&bytes)
+            .ok_or_else(|| D::Error::custom("invalid multicodec public key"))
+    }
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[derive(Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[borsh(use_discriminant = true)]
 #[non_exhaustive]
 pub enum DeviceSignature {
@@ -46,52 +168,105 @@ pub enum DeviceSignature {
             deserialize_with = "util::ed25519::deserialize_signature",
             serialize_with = "util::ed25519::serialize_signature"
         )]
+        #[cfg_attr(feature = "serde", serde(with = "util::serde_hex"))]
         Ed25519Signature,
     ),
+    /// An ECDSA signature over the protocol message digest, with a low `s`.
+    Secp256k1(
+        #[borsh(
+            deserialize_with = "util::secp256k1::deserialize_signature",
+            serialize_with = "util::secp256k1::serialize_signature"
+        )]
+        #[cfg_attr(feature = "serde", serde(with = "util::serde_hex"))]
+        Secp256k1Signature,
+    ),
+    /// An ECDSA signature over the (truncated) protocol message digest.
+    P256(
+        #[borsh(
+            deserialize_with = "util::p256::deserialize_signature",
+            serialize_with = "util::p256::serialize_signature"
+        )]
+        #[cfg_attr(feature = "serde", serde(with = "util::serde_hex"))]
+        P256Signature,
+    ),
+    /// A WebAuthn assertion by a [`P256`] passkey.
+    ///
+    /// [`P256`]: P256VerifyingKey
+    WebAuthn(WebAuthnSignature),
+    /// An encoded ML-DSA-44 signature over the protocol message digest.
+    #[cfg(feature = "ml-dsa")]
+    MlDsa44(
+        #[borsh(deserialize_with = "util::ml_dsa::deserialize_signature")]
+        #[cfg_attr(feature = "serde", serde(with = "util::serde_hex"))]
+        Box<[u8; util::ml_dsa::SIGNATURE_LEN]>,
+    ),
 }
 
 impl Device {
     /// Domain separation context.
+    ///
+    /// Devices sign the [`Sha512`] digest of the context and message with
+    /// pure Ed25519 (rather than Ed25519ph), so that signatures can be
+    /// batch verified. ECDSA devices sign the digest as a prehash, and
+    /// ML-DSA devices sign it as a message with an empty context.
+    ///
+    /// Ed25519ph signatures (i.e. those of [`GroupSignature`]s prior to
+    /// version `3`, and the device logs they extend) do not verify, and must
+    /// be re-signed.
+    ///
+    /// [`GroupSignature`]: crate::GroupSignature
     pub const SIGNING_CONTEXT: &[u8] = b"datalove::persona";
 
-    // /// Initializes a new [`Device`] and it's [`MerkleLog`].
-    // pub fn init<Si>(pk: Ed25519VerifyingKey, signer: &Si) -> Result<Self, Error>
-    // where
-    //     Si: DigestSigner<Sha512, DeviceSignature>,
-    // {
-    //     let entry = {
-    //         let entry = Self::protocol_message_digest(pk.as_bytes());
-    //         // assert that the signer is the owner of the public key being initialized
-    //         let DeviceSignature::Ed25519(sig) = signer
-    //             .try_sign_digest(entry.clone())
-    //             .map_err(Error::SignatureError)?;
-    //         pk.verify_digest(entry, &sig)
-    //             .map_err(Error::SignatureError)?;
-    //         sig
-    //     };
-
-    //     Ok(Self::Ed25519(pk))
-    // }
+    /// Initializes a new [`Device`] and its [`DeviceLog`], whose first entry
+    /// is the device's own public key.
+    pub fn init<K, Si>(pk: K, signer: &Si) -> Result<(Self, DeviceLog), Error>
+    where
+        K: Into<Self>,
+        Si: DigestSigner<Sha512, DeviceSignature>,
+    {
+        let mut device: Self = pk.into();
+        let mut log = DeviceLog::default();
+        let inner = device.inner.clone();
+        device.append(&inner, signer, &mut log)?;
+        Ok((device, log))
+    }
 
-    /*
-    pub fn append<T, Si, St>(
+    /// Signs and appends an entry to the device's log, advancing its head.
+    pub fn append<T, Si>(
         &mut self,
         entry: &T,
         signer: &Si,
-        store: &mut St,
-    ) -> Result<Signature, Error>
+        log: &mut DeviceLog,
+    ) -> Result<DeviceSignature, Error>
     where
         T: BorshSerialize,
-        Si: DigestSigner<Sha512, Signature>,
-        St: Store<DeviceLogNode>,
+        Si: DigestSigner<Sha512, DeviceSignature>,
     {
-        let entry_bytes = borsh::to_vec(entry)?;
-        let entry = Self::protocol_message_digest(&entry_bytes);
-        let signature = signer.try_sign_digest(entry)?;
-        self.log.append(signature.to_bytes(), store)?;
+        if log.len() != self.log.len() || log.head() != self.log {
+            return Err(Error::InvalidOperation("log does not match device head"));
+        }
+
+        let entry: Sha256Digest = Sha256Pipe::encode_to_writer(entry, util::Empty)?.into();
+        let msg = DeviceLogNode::message(&self.log, &entry);
+        let signature = self.sign_message::<Sha512, _>(&msg, signer)?;
+
+        log.push(DeviceLogNode::new(self.log, entry, signature.clone()));
+        self.log = log.head();
         Ok(signature)
     }
-     */
+
+    /// Fast-forwards the device to a newer head of its log.
+    pub fn fast_forward(&mut self, head: LogHead, proof: &ConsistencyProof) -> Result<(), Error> {
+        head.verify_extension(&self.log, proof)?;
+        self.log = head;
+        Ok(())
+    }
+
+    /// Sets the head of the device's log, which must have already been
+    /// verified to extend the current head.
+    pub(crate) fn set_head(&mut self, head: LogHead) {
+        self.log = head;
+    }
 
     // /// Determines if this is a "null" peer, i.e. the default peer.
     // pub fn is_null_peer(&self) -> bool {
@@ -114,10 +289,32 @@ impl Device {
     where
         D: Digest<OutputSize = U64>,
     {
-        match (self.inner, signature) {
-            (DeviceInner::Ed25519(pk), DeviceSignature::Ed25519(sig)) => {
-                Ok(pk.verify_digest(msg_digest, sig)?)
+        let msg_digest = msg_digest.finalize();
+        match (&self.inner, signature) {
+            (DeviceInner::Ed25519(pk), DeviceSignature::Ed25519(sig)) => Ok(
+                ed25519_dalek::verify_batch(&[msg_digest.as_slice()], &[*sig], &[*pk])?,
+            ),
+            (DeviceInner::Secp256k1(pk), DeviceSignature::Secp256k1(sig)) => {
+                Ok(pk.verify_prehash(msg_digest.as_slice(), sig)?)
+            }
+            (DeviceInner::P256(pk), DeviceSignature::P256(sig)) => {
+                Ok(pk.verify_prehash(msg_digest.as_slice(), sig)?)
+            }
+            (DeviceInner::P256(pk), DeviceSignature::WebAuthn(sig)) => {
+                sig.verify(pk, msg_digest.as_slice())
+            }
+            #[cfg(feature = "ml-dsa")]
+            (DeviceInner::MlDsa44(pk), DeviceSignature::MlDsa44(sig)) => {
+                let sig = util::ml_dsa::decode_signature(sig)
+                    .ok_or(Error::InvalidSignatureError("invalid ml-dsa signature"))?;
+                util::ml_dsa::decode_key(pk)
+                    .verify_with_context(msg_digest.as_slice(), &[], &sig)
+                    .then_some(())
+                    .ok_or(Error::InvalidSignatureError("invalid ml-dsa signature"))
             }
+            _ => Err(Error::InvalidSignatureError(
+                "signature / device type mismatch",
+            )),
         }
     }
 
@@ -129,6 +326,43 @@ impl Device {
     }
 }
 
+impl From<Ed25519VerifyingKey> for Device {
+    fn from(pk: Ed25519VerifyingKey) -> Self {
+        Self {
+            inner: DeviceInner::Ed25519(pk),
+            log: LogHead::EMPTY,
+        }
+    }
+}
+
+impl From<Secp256k1VerifyingKey> for Device {
+    fn from(pk: Secp256k1VerifyingKey) -> Self {
+        Self {
+            inner: DeviceInner::Secp256k1(pk),
+            log: LogHead::EMPTY,
+        }
+    }
+}
+
+impl From<P256VerifyingKey> for Device {
+    fn from(pk: P256VerifyingKey) -> Self {
+        Self {
+            inner: DeviceInner::P256(pk),
+            log: LogHead::EMPTY,
+        }
+    }
+}
+
+#[cfg(feature = "ml-dsa")]
+impl From<MlDsa44VerifyingKey<MlDsa44>> for Device {
+    fn from(pk: MlDsa44VerifyingKey<MlDsa44>) -> Self {
+        Self {
+            inner: DeviceInner::MlDsa44(Box::new(util::ml_dsa::encode_key(&pk))),
+            log: LogHead::EMPTY,
+        }
+    }
+}
+
 // impl AsRef<Ed25519VerifyingKey> for Device {
 //     fn as_ref(&self) -> &Ed25519VerifyingKey {
 //         self.public_key()
@@ -147,19 +381,220 @@ impl Verifier<DeviceSignature> for Device {
     }
 }
 
+/// Signs protocol message digests with an [`Ed25519SigningKey`].
+impl DigestSigner<Sha512, DeviceSignature> for Ed25519SigningKey {
+    fn try_sign_digest(&self, msg_digest: Sha512) -> Result<DeviceSignature, SignatureError> {
+        let sig = Signer::<Ed25519Signature>::try_sign(self, msg_digest.finalize().as_slice())?;
+        Ok(DeviceSignature::Ed25519(sig))
+    }
+}
+
+/// Signs protocol message digests as prehashes with a [`Secp256k1SigningKey`].
+impl DigestSigner<Sha512, DeviceSignature> for Secp256k1SigningKey {
+    fn try_sign_digest(&self, msg_digest: Sha512) -> Result<DeviceSignature, SignatureError> {
+        let sig: Secp256k1Signature = self.sign_prehash(msg_digest.finalize().as_slice())?;
+        Ok(DeviceSignature::Secp256k1(sig))
+    }
+}
+
+/// Signs protocol message digests as prehashes with a [`P256SigningKey`].
+impl DigestSigner<Sha512, DeviceSignature> for P256SigningKey {
+    fn try_sign_digest(&self, msg_digest: Sha512) -> Result<DeviceSignature, SignatureError> {
+        let sig: P256Signature = self.sign_prehash(msg_digest.finalize().as_slice())?;
+        Ok(DeviceSignature::P256(sig))
+    }
+}
+
+/// Signs protocol message digests deterministically with an
+/// [`MlDsa44SigningKey`].
+#[cfg(feature = "ml-dsa")]
+impl DigestSigner<Sha512, DeviceSignature> for MlDsa44SigningKey<MlDsa44> {
+    fn try_sign_digest(&self, msg_digest: Sha512) -> Result<DeviceSignature, SignatureError> {
+        let sig = self
+            .sign_deterministic(msg_digest.finalize().as_slice(), &[])
+            .map_err(|_| SignatureError::new())?;
+        let sig = util::ml_dsa::encode_signature(&sig);
+        Ok(DeviceSignature::MlDsa44(Box::new(sig)))
+    }
+}
+
+/// A batch of [`Device`] signatures over protocol messages, verified at once.
+///
+/// Ed25519 signatures are always verified with the (cofactored) batch
+/// equation, whether batched or alone (as by [`Device::verify`]), so that a
+/// signature's validity never depends on its co-signers.
+#[derive(Debug, Default)]
+pub(crate) struct DeviceBatch {
+    messages: Vec<[u8; 64]>,
+    signatures: Vec<Ed25519Signature>,
+    keys: Vec<Ed25519VerifyingKey>,
+}
+
+impl DeviceBatch {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            messages: Vec::with_capacity(capacity),
+            signatures: Vec::with_capacity(capacity),
+            keys: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Adds a device's signature over a protocol message to the batch.
+    ///
+    /// Only [`Ed25519`] signatures can be batched, so others are verified
+    /// immediately.
+    ///
+    /// [`Ed25519`]: Ed25519Signature
+    pub fn push(
+        &mut self,
+        device: &Device,
+        msg: &[u8],
+        signature: &DeviceSignature,
+    ) -> Result<(), Error> {
+        let msg_digest = Device::protocol_message_digest::<Sha512>(msg);
+        match (&device.inner, signature) {
+            (DeviceInner::Ed25519(pk), DeviceSignature::Ed25519(sig)) => {
+                let mut message = [0u8; 64];
+                message.copy_from_slice(&msg_digest.finalize());
+                self.messages.push(message);
+                self.signatures.push(*sig);
+                self.keys.push(*pk);
+                Ok(())
+            }
+            _ => device.verify_digest(msg_digest, signature),
+        }
+    }
+
+    /// Verifies all signatures in the batch, failing if any are invalid.
+    pub fn verify(self) -> Result<(), Error> {
+        if self.keys.is_empty() {
+            return Ok(());
+        }
+
+        let messages = self.messages.iter().map(|m| &m[..]).collect::<Vec<_>>();
+        Ok(ed25519_dalek::verify_batch(
+            &messages,
+            &self.signatures,
+            &self.keys,
+        )?)
+    }
+}
+
 /// A default [`Device`] with a secret key of all zeros.
 impl Default for Device {
     fn default() -> Self {
         let sk = Ed25519SigningKey::from_bytes(&[0u8; 32]);
-        let pk = sk.verifying_key();
-        Self {
-            inner: DeviceInner::Ed25519(pk),
-        }
+        Self::from(sk.verifying_key())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    #[test]
+    fn can_batch_verify() -> Result<(), Error> {
+        let keys = (1..=3u8)
+            .map(|seed| Ed25519SigningKey::from_bytes(&[seed; 32]))
+            .collect::<Vec<_>>();
+        let devices = keys
+            .iter()
+            .map(|sk| Device::from(sk.verifying_key()))
+            .collect::<Vec<_>>();
+        let sigs = devices
+            .iter()
+            .zip(keys.iter())
+            .map(|(device, sk)| device.sign_message::<Sha512, _>(b"hello world", sk))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut batch = DeviceBatch::with_capacity(devices.len());
+        for (device, sig) in devices.iter().zip(sigs.iter()) {
+            batch.push(device, b"hello world", sig)?;
+        }
+        batch.verify()?;
+
+        // batches of one are verified as larger batches are
+        let mut batch = DeviceBatch::default();
+        batch.push(&devices[0], b"hello world", &sigs[0])?;
+        batch.verify()?;
+        devices[0].verify(b"hello world", &sigs[0])?;
+
+        // any invalid signature fails the batch
+        let mut batch = DeviceBatch::default();
+        batch.push(&devices[0], b"hello world", &sigs[0])?;
+        batch.push(&devices[1], b"goodbye world", &sigs[1])?;
+        assert!(batch.verify().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn can_verify_ecdsa_devices() -> Result<(), Error> {
+        let k256_sk = Secp256k1SigningKey::from_slice(&[1; 32]).unwrap();
+        let p256_sk = P256SigningKey::from_slice(&[1; 32]).unwrap();
+        let (k256_device, _) = Device::init(*k256_sk.verifying_key(), &k256_sk)?;
+        let (p256_device, _) = Device::init(*p256_sk.verifying_key(), &p256_sk)?;
+        assert_ne!(k256_device.id(), p256_device.id());
+
+        let k256_sig = k256_device.sign_message::<Sha512, _>(b"hello world", &k256_sk)?;
+        let p256_sig = p256_device.sign_message::<Sha512, _>(b"hello world", &p256_sk)?;
+        k256_device.verify(b"hello world", &k256_sig)?;
+        p256_device.verify(b"hello world", &p256_sig)?;
+        assert!(k256_device.verify(b"goodbye world", &k256_sig).is_err());
+        assert!(k256_device.verify(b"hello world", &p256_sig).is_err());
+
+        // ecdsa signatures are verified alongside batched signatures
+        let ed25519_sk = Ed25519SigningKey::from_bytes(&[1; 32]);
+        let ed25519_device = Device::from(ed25519_sk.verifying_key());
+        let ed25519_sig = ed25519_device.sign_message::<Sha512, _>(b"hello world", &ed25519_sk)?;
+        let mut batch = DeviceBatch::default();
+        batch.push(&ed25519_device, b"hello world", &ed25519_sig)?;
+        batch.push(&k256_device, b"hello world", &k256_sig)?;
+        batch.push(&p256_device, b"hello world", &p256_sig)?;
+        batch.verify()?;
+        assert!(DeviceBatch::default()
+            .push(&p256_device, b"hello world", &k256_sig)
+            .is_err());
+
+        // keys and signatures roundtrip through their encodings
+        for (device, sig) in [(k256_device, k256_sig), (p256_device, p256_sig)] {
+            assert_eq!(Device::try_from_slice(&borsh::to_vec(&device)?)?, device);
+            assert_eq!(DeviceSignature::try_from_slice(&borsh::to_vec(&sig)?)?, sig);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "ml-dsa")]
+    #[test]
+    fn can_verify_ml_dsa_devices() -> Result<(), Error> {
+        use ml_dsa::KeyGen;
+
+        let keypair = MlDsa44::key_gen_internal(&[1; 32].into());
+        let sk = keypair.signing_key();
+        let (device, _) = Device::init(keypair.verifying_key().clone(), sk)?;
+
+        let sig = device.sign_message::<Sha512, _>(b"hello world", sk)?;
+        device.verify(b"hello world", &sig)?;
+        assert!(device.verify(b"goodbye world", &sig).is_err());
+
+        // ml-dsa signatures are verified alongside batched signatures
+        let ed25519_sk = Ed25519SigningKey::from_bytes(&[1; 32]);
+        let ed25519_device = Device::from(ed25519_sk.verifying_key());
+        let ed25519_sig = ed25519_device.sign_message::<Sha512, _>(b"hello world", &ed25519_sk)?;
+        assert!(ed25519_device.verify(b"hello world", &sig).is_err());
+        let mut batch = DeviceBatch::default();
+        batch.push(&ed25519_device, b"hello world", &ed25519_sig)?;
+        batch.push(&device, b"hello world", &sig)?;
+        batch.verify()?;
+
+        // keys and signatures roundtrip through their encodings
+        assert_eq!(Device::try_from_slice(&borsh::to_vec(&device)?)?, device);
+        assert_eq!(DeviceSignature::try_from_slice(&borsh::to_vec(&sig)?)?, sig);
+        let mut bytes = borsh::to_vec(&sig)?;
+        bytes[1..].fill(0xff);
+        assert!(DeviceSignature::try_from_slice(&bytes).is_err());
+        Ok(())
+    }
+
     // #[test]
     // fn default() {
     //     use super::*;
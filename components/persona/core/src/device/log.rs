@@ -0,0 +1,558 @@
+//! An append-only log of a [`Device`]'s signed entries, committed to as an
+//! [RFC 6962](https://www.rfc-editor.org/rfc/rfc6962#section-2.1) Merkle tree.
+
+use super::{Device, DeviceSignature};
+use crate::{
+    maybestd::vec::Vec,
+    util::{risc0::Sha256, Sha256Digest},
+    Error,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use digest::Digest;
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// The head of a [`Device`]'s log.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogHead {
+    /// The number of entries in the log.
+    len: u64,
+    /// Merkle root of the log's entries.
+    root: Sha256Digest,
+}
+
+impl LogHead {
+    pub const EMPTY: Self = Self {
+        len: 0,
+        root: Sha256Digest::ZERO,
+    };
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn root(&self) -> &Sha256Digest {
+        &self.root
+    }
+
+    /// Asserts this head extends (or is equal to) a previous head of the
+    /// same log.
+    pub fn verify_extension(&self, prev: &Self, proof: &ConsistencyProof) -> Result<(), Error> {
+        proof.verify(prev, self)
+    }
+}
+
+/// A signed entry of a [`Device`]'s log.
+#[derive(Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+pub struct DeviceLogNode {
+    /// The head of the log prior to appending this entry.
+    prev: LogHead,
+    /// Digest of the borsh-serialized entry.
+    entry: Sha256Digest,
+    /// The device's signature over the previous head and entry.
+    signature: DeviceSignature,
+}
+
+impl DeviceLogNode {
+    pub(super) fn new(prev: LogHead, entry: Sha256Digest, signature: DeviceSignature) -> Self {
+        Self {
+            prev,
+            entry,
+            signature,
+        }
+    }
+
+    pub fn prev(&self) -> &LogHead {
+        &self.prev
+    }
+
+    pub fn entry(&self) -> &Sha256Digest {
+        &self.entry
+    }
+
+    pub fn signature(&self) -> &DeviceSignature {
+        &self.signature
+    }
+
+    /// The message signed by the device when appending an entry to its log.
+    pub fn message(prev: &LogHead, entry: &Sha256Digest) -> Vec<u8> {
+        borsh::to_vec(&(prev, entry)).expect("should never fail to serialize")
+    }
+
+    /// Digest of the node, as a leaf of the log.
+    pub fn leaf_hash(&self) -> Sha256Digest {
+        let bytes = borsh::to_vec(self).expect("should never fail to serialize");
+        Sha256::new_with_prefix(LEAF_PREFIX)
+            .chain_update(bytes)
+            .into()
+    }
+}
+
+/// The full contents of a [`Device`]'s log, from which its head and proofs
+/// are derived.
+#[derive(Clone, Debug, Default, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+pub struct DeviceLog {
+    nodes: Vec<DeviceLogNode>,
+}
+
+impl DeviceLog {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.nodes.len() as u64
+    }
+
+    pub fn get(&self, index: u64) -> Option<&DeviceLogNode> {
+        self.nodes.get(index as usize)
+    }
+
+    /// Returns an iterator over the log's nodes, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &DeviceLogNode> {
+        self.nodes.iter()
+    }
+
+    /// Computes the current head of the log.
+    pub fn head(&self) -> LogHead {
+        LogHead {
+            len: self.len(),
+            root: subtree_root(&self.leaves()),
+        }
+    }
+
+    /// Produces a proof of the inclusion of the node at the given index
+    /// within the log's current head.
+    pub fn inclusion_proof(&self, index: u64) -> Result<InclusionProof, Error> {
+        if index >= self.len() {
+            return Err(Error::InvalidMerkleProof("log index out of bounds"));
+        }
+
+        let mut path = Vec::new();
+        inclusion_path(&self.leaves(), index as usize, &mut path);
+        Ok(InclusionProof { index, path })
+    }
+
+    /// Produces a proof that the log's current head extends its head of the
+    /// given length.
+    pub fn consistency_proof(&self, prev_len: u64) -> Result<ConsistencyProof, Error> {
+        if prev_len > self.len() {
+            return Err(Error::InvalidMerkleProof("log length out of bounds"));
+        }
+
+        let mut path = Vec::new();
+        if prev_len > 0 {
+            consistency_path(&self.leaves(), prev_len as usize, true, &mut path);
+        }
+        Ok(ConsistencyProof { path })
+    }
+
+    /// Proves that the log's last node extends the given, previously known
+    /// head of the log.
+    pub fn extension(&self, known: &LogHead) -> Result<LogExtension, Error> {
+        let node = self
+            .nodes
+            .last()
+            .ok_or(Error::InvalidOperation("log is empty"))?;
+
+        Ok(LogExtension {
+            prev: node.prev,
+            signature: node.signature.clone(),
+            head: self.head(),
+            inclusion: self.inclusion_proof(self.len() - 1)?,
+            consistency: self.consistency_proof(known.len)?,
+        })
+    }
+
+    pub(super) fn push(&mut self, node: DeviceLogNode) {
+        self.nodes.push(node);
+    }
+
+    fn leaves(&self) -> Vec<Sha256Digest> {
+        self.nodes.iter().map(DeviceLogNode::leaf_hash).collect()
+    }
+}
+
+/// Proof of a node's inclusion within a [`LogHead`].
+#[derive(Clone, Debug, Default, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InclusionProof {
+    /// Index of the node within the log.
+    index: u64,
+    /// Siblings along the path from the leaf to the root.
+    path: Vec<Sha256Digest>,
+}
+
+impl InclusionProof {
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Verifies the inclusion of a leaf within the log.
+    pub fn verify(&self, leaf: &Sha256Digest, head: &LogHead) -> Result<(), Error> {
+        if self.index >= head.len {
+            return Err(Error::InvalidMerkleProof("log index out of bounds"));
+        }
+
+        let (mut fnode, mut snode) = (self.index, head.len - 1);
+        let mut root = *leaf;
+        for sibling in self.path.iter() {
+            if snode == 0 {
+                return Err(Error::InvalidMerkleProof("log proof too long"));
+            }
+
+            if fnode & 1 == 1 || fnode == snode {
+                root = node_hash(sibling, &root);
+                while fnode & 1 == 0 && fnode != 0 {
+                    fnode >>= 1;
+                    snode >>= 1;
+                }
+            } else {
+                root = node_hash(&root, sibling);
+            }
+            fnode >>= 1;
+            snode >>= 1;
+        }
+
+        if snode != 0 || root != head.root {
+            return Err(Error::InvalidMerkleProof("log proof does not match head"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Proof that a [`LogHead`] extends a previous head of the same log.
+#[derive(Clone, Debug, Default, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConsistencyProof {
+    path: Vec<Sha256Digest>,
+}
+
+impl ConsistencyProof {
+    /// Verifies that `head` extends `prev`.
+    pub fn verify(&self, prev: &LogHead, head: &LogHead) -> Result<(), Error> {
+        let invalid = || Err(Error::InvalidMerkleProof("log heads are inconsistent"));
+
+        if prev.len > head.len {
+            return invalid();
+        }
+        if prev.len == 0 || prev.len == head.len {
+            // an empty log is extended by all logs, and equal-length logs must be equal
+            let expected_root = match prev.len {
+                0 => &Sha256Digest::ZERO,
+                _ => &head.root,
+            };
+            let is_valid = self.path.is_empty() && &prev.root == expected_root;
+            return if is_valid { Ok(()) } else { invalid() };
+        }
+
+        // if the previous log is a complete subtree, its root begins the path
+        let mut path = self.path.iter();
+        let first = if prev.len.is_power_of_two() {
+            &prev.root
+        } else {
+            match path.next() {
+                Some(first) => first,
+                None => return invalid(),
+            }
+        };
+
+        let (mut fnode, mut snode) = (prev.len - 1, head.len - 1);
+        while fnode & 1 == 1 {
+            fnode >>= 1;
+            snode >>= 1;
+        }
+
+        let (mut froot, mut sroot) = (*first, *first);
+        for sibling in path {
+            if snode == 0 {
+                return invalid();
+            }
+
+            if fnode & 1 == 1 || fnode == snode {
+                froot = node_hash(sibling, &froot);
+                sroot = node_hash(sibling, &sroot);
+                while fnode & 1 == 0 && fnode != 0 {
+                    fnode >>= 1;
+                    snode >>= 1;
+                }
+            } else {
+                sroot = node_hash(&sroot, sibling);
+            }
+            fnode >>= 1;
+            snode >>= 1;
+        }
+
+        if snode != 0 || froot != prev.root || sroot != head.root {
+            return invalid();
+        }
+
+        Ok(())
+    }
+}
+
+/// A [`Device`]'s signature over the last entry appended to its log, along
+/// with proof that the resulting head extends a previously known head.
+#[derive(Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogExtension {
+    /// The head of the log prior to appending the entry.
+    prev: LogHead,
+    /// The device's signature over the previous head and entry.
+    signature: DeviceSignature,
+    /// The head of the log after appending the entry.
+    head: LogHead,
+    /// Proof of the signed node's inclusion as the last node of `head`.
+    inclusion: InclusionProof,
+    /// Proof that `head` extends the known head.
+    consistency: ConsistencyProof,
+}
+
+impl LogExtension {
+    pub fn prev(&self) -> &LogHead {
+        &self.prev
+    }
+
+    pub fn signature(&self) -> &DeviceSignature {
+        &self.signature
+    }
+
+    /// The head of the log after appending the entry.
+    pub fn head(&self) -> &LogHead {
+        &self.head
+    }
+
+    /// Asserts the entry is the last node of the new head, which strictly
+    /// extends the known head.
+    ///
+    /// Does not verify the signature itself, which is left to the caller.
+    pub fn verify(&self, known: &LogHead, entry: &Sha256Digest) -> Result<(), Error> {
+        if self.prev.len.checked_add(1) != Some(self.head.len)
+            || self.inclusion.index != self.prev.len
+            || self.head.len <= known.len
+        {
+            return Err(Error::InvalidMerkleProof(
+                "log extension does not append the entry",
+            ));
+        }
+
+        let node = DeviceLogNode::new(self.prev, *entry, self.signature.clone());
+        self.inclusion.verify(&node.leaf_hash(), &self.head)?;
+        self.head.verify_extension(known, &self.consistency)
+    }
+}
+
+impl Device {
+    /// Asserts the entry was signed by this device and appended to its log,
+    /// extending its current head.
+    pub fn verify_log_extension(
+        &self,
+        entry: &Sha256Digest,
+        extension: &LogExtension,
+    ) -> Result<(), Error> {
+        extension.verify(self.head(), entry)?;
+        signature::Verifier::verify(
+            self,
+            &DeviceLogNode::message(&extension.prev, entry),
+            &extension.signature,
+        )?;
+        Ok(())
+    }
+
+    /// Asserts the node was signed by this device and is included in its log.
+    pub fn verify_log_node(
+        &self,
+        node: &DeviceLogNode,
+        proof: &InclusionProof,
+    ) -> Result<(), Error> {
+        if node.prev.len != proof.index {
+            return Err(Error::InvalidMerkleProof(
+                "log node does not match its index",
+            ));
+        }
+
+        signature::Verifier::verify(
+            self,
+            &DeviceLogNode::message(&node.prev, &node.entry),
+            &node.signature,
+        )?;
+        proof.verify(&node.leaf_hash(), self.head())
+    }
+}
+
+fn node_hash(left: &Sha256Digest, right: &Sha256Digest) -> Sha256Digest {
+    Sha256::new_with_prefix(NODE_PREFIX)
+        .chain_update(left)
+        .chain_update(right)
+        .into()
+}
+
+/// Computes the root of a subtree of leaves (i.e. MTH).
+fn subtree_root(leaves: &[Sha256Digest]) -> Sha256Digest {
+    match leaves {
+        [] => Sha256Digest::ZERO,
+        [leaf] => *leaf,
+        _ => {
+            let (left, right) = leaves.split_at(split_point(leaves.len()));
+            node_hash(&subtree_root(left), &subtree_root(right))
+        }
+    }
+}
+
+/// Computes the inclusion path of a leaf (i.e. PATH), from leaf to root.
+fn inclusion_path(leaves: &[Sha256Digest], index: usize, path: &mut Vec<Sha256Digest>) {
+    if leaves.len() <= 1 {
+        return;
+    }
+
+    let (left, right) = leaves.split_at(split_point(leaves.len()));
+    if index < left.len() {
+        inclusion_path(left, index, path);
+        path.push(subtree_root(right));
+    } else {
+        inclusion_path(right, index - left.len(), path);
+        path.push(subtree_root(left));
+    }
+}
+
+/// Computes the consistency path between a log and its prefix of length
+/// `prev_len` (i.e. SUBPROOF).
+fn consistency_path(
+    leaves: &[Sha256Digest],
+    prev_len: usize,
+    is_complete: bool,
+    path: &mut Vec<Sha256Digest>,
+) {
+    if prev_len == leaves.len() {
+        if !is_complete {
+            path.push(subtree_root(leaves));
+        }
+        return;
+    }
+
+    let (left, right) = leaves.split_at(split_point(leaves.len()));
+    if prev_len <= left.len() {
+        consistency_path(left, prev_len, is_complete, path);
+        path.push(subtree_root(right));
+    } else {
+        consistency_path(right, prev_len - left.len(), false, path);
+        path.push(subtree_root(left));
+    }
+}
+
+/// The largest power of two less than `len`, for `len > 1`.
+#[inline]
+fn split_point(len: usize) -> usize {
+    1 << (usize::BITS - 1 - (len - 1).leading_zeros())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::SigningKey;
+
+    fn init_log(num_entries: u8) -> Result<(SigningKey, Device, DeviceLog), Error> {
+        let sk = SigningKey::from_bytes(&[1; 32]);
+        let (mut device, mut log) = Device::init(sk.verifying_key(), &sk)?;
+        for entry in 1..num_entries {
+            device.append(&entry, &sk, &mut log)?;
+        }
+        Ok((sk, device, log))
+    }
+
+    #[test]
+    fn can_prove_inclusion() -> Result<(), Error> {
+        let (_, device, log) = init_log(7)?;
+        assert_eq!(device.head(), &log.head());
+        assert_eq!(device.head().len(), 7);
+
+        for (index, node) in log.iter().enumerate() {
+            let proof = log.inclusion_proof(index as u64)?;
+            device.verify_log_node(node, &proof)?;
+            assert!(device
+                .verify_log_node(node, &log.inclusion_proof((index as u64 + 1) % 7)?)
+                .is_err());
+        }
+        assert!(log.inclusion_proof(7).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn can_prove_consistency() -> Result<(), Error> {
+        let (_, _, log) = init_log(9)?;
+
+        let heads = (0..=log.len())
+            .map(|len| DeviceLog {
+                nodes: log.nodes[..len as usize].to_vec(),
+            })
+            .map(|log| log.head())
+            .collect::<Vec<_>>();
+        for prev in heads.iter() {
+            let proof = log.consistency_proof(prev.len())?;
+            log.head().verify_extension(prev, &proof)?;
+
+            // proofs are only valid for the heads they were produced for
+            let other = &heads[prev.len() as usize % 9 + 1];
+            assert!(log.head().verify_extension(other, &proof).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn can_fast_forward() -> Result<(), Error> {
+        let (sk, mut device, mut log) = init_log(3)?;
+        let mut peer = device.clone();
+
+        device.append(b"hello world", &sk, &mut log)?;
+        device.append(b"goodbye world", &sk, &mut log)?;
+        let proof = log.consistency_proof(peer.head().len())?;
+        peer.fast_forward(*device.head(), &proof)?;
+        assert_eq!(peer, device);
+
+        // cannot append to a log that does not match the device's head
+        let mut stale = DeviceLog::default();
+        assert!(peer.append(b"hello world", &sk, &mut stale).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn can_prove_extension() -> Result<(), Error> {
+        let (sk, mut device, mut log) = init_log(3)?;
+        let known = *device.head();
+        let mut fork = log.clone();
+
+        device.append(b"hello world", &sk, &mut log)?;
+        device.append(b"goodbye world", &sk, &mut log)?;
+        let entry = *log.get(4).unwrap().entry();
+        let extension = log.extension(&known)?;
+        assert_eq!(extension.head(), device.head());
+
+        let mut peer = device.clone();
+        peer.set_head(known);
+        peer.verify_log_extension(&entry, &extension)?;
+
+        // extension must sign the entry, and strictly extend the known head
+        assert!(peer
+            .verify_log_extension(log.get(3).unwrap().entry(), &extension)
+            .is_err());
+        assert!(device.verify_log_extension(&entry, &extension).is_err());
+
+        // extensions of forked logs are rejected
+        let mut forked = peer;
+        for entry in 0..3u8 {
+            forked.append(&entry, &sk, &mut fork)?;
+        }
+        let extension = fork.extension(device.head())?;
+        assert!(device
+            .verify_log_extension(fork.get(5).unwrap().entry(), &extension)
+            .is_err());
+        Ok(())
+    }
+}
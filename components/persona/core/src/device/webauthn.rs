@@ -0,0 +1,180 @@
+//! [WebAuthn](https://www.w3.org/TR/webauthn-3/) assertions as [`Device`]
+//! signatures, allowing passkeys to act as devices without native key storage.
+
+use super::Device;
+use crate::{
+    maybestd::vec::Vec,
+    util::{self, risc0::Sha256},
+    Error,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use borsh::{BorshDeserialize, BorshSerialize};
+use digest::Digest;
+use p256::ecdsa::{Signature as P256Signature, VerifyingKey as P256VerifyingKey};
+use sha2::Sha512;
+use signature::Verifier;
+
+/// The serialized prefix of client data for an assertion, up to its challenge.
+const CLIENT_DATA_PREFIX: &[u8] = br#"{"type":"webauthn.get","challenge":""#;
+
+/// Authenticator data is at least an RP ID hash, flags and signature counter.
+const AUTHENTICATOR_DATA_MIN_LEN: usize = 37;
+const FLAGS_INDEX: usize = 32;
+const USER_PRESENT: u8 = 0x01;
+
+/// A passkey's assertion, signing its authenticator data and the digest of
+/// client data whose challenge is a protocol message digest.
+#[derive(Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WebAuthnSignature {
+    /// The authenticator data, as returned by the authenticator.
+    #[cfg_attr(feature = "serde", serde(with = "util::serde_hex"))]
+    authenticator_data: Vec<u8>,
+    /// The JSON-serialized client data, as returned by the client.
+    #[cfg_attr(feature = "serde", serde(with = "util::serde_hex"))]
+    client_data_json: Vec<u8>,
+    /// The passkey's signature over `authenticator_data || sha256(client_data_json)`.
+    #[borsh(
+        deserialize_with = "util::p256::deserialize_signature",
+        serialize_with = "util::p256::serialize_signature"
+    )]
+    #[cfg_attr(feature = "serde", serde(with = "util::serde_hex"))]
+    signature: P256Signature,
+}
+
+impl WebAuthnSignature {
+    pub fn new(
+        authenticator_data: Vec<u8>,
+        client_data_json: Vec<u8>,
+        signature: P256Signature,
+    ) -> Self {
+        Self {
+            authenticator_data,
+            client_data_json,
+            signature,
+        }
+    }
+
+    /// The challenge a passkey must sign to sign a protocol message, i.e. the
+    /// message's protocol digest.
+    pub fn challenge(message: &[u8]) -> [u8; 64] {
+        let mut challenge = [0u8; 64];
+        challenge.copy_from_slice(&Device::protocol_message_digest::<Sha512>(message).finalize());
+        challenge
+    }
+
+    pub fn authenticator_data(&self) -> &[u8] {
+        &self.authenticator_data
+    }
+
+    pub fn client_data_json(&self) -> &[u8] {
+        &self.client_data_json
+    }
+
+    pub fn signature(&self) -> &P256Signature {
+        &self.signature
+    }
+
+    /// Asserts the client data is a `webauthn.get` assertion whose challenge
+    /// is the protocol message digest, that the authenticator attests to the
+    /// user's presence, and that the passkey signed both.
+    ///
+    /// Client data is checked following WebAuthn's limited verification
+    /// algorithm, i.e. against its serialized prefix. The relying party and
+    /// origin are left unchecked, as any relying party may hold a passkey.
+    pub(super) fn verify(&self, pk: &P256VerifyingKey, msg_digest: &[u8]) -> Result<(), Error> {
+        let challenge = URL_SAFE_NO_PAD.encode(msg_digest);
+        let is_challenge = self
+            .client_data_json
+            .strip_prefix(CLIENT_DATA_PREFIX)
+            .and_then(|rest| rest.strip_prefix(challenge.as_bytes()))
+            .is_some_and(|rest| rest.starts_with(b"\""));
+        if !is_challenge {
+            return Err(Error::InvalidSignatureError(
+                "webauthn challenge does not match message",
+            ));
+        }
+
+        let is_user_present = self.authenticator_data.len() >= AUTHENTICATOR_DATA_MIN_LEN
+            && self.authenticator_data[FLAGS_INDEX] & USER_PRESENT != 0;
+        if !is_user_present {
+            return Err(Error::InvalidSignatureError(
+                "webauthn authenticator data must attest to user presence",
+            ));
+        }
+
+        let client_data_hash = Sha256::digest(&self.client_data_json);
+        let message = [&self.authenticator_data[..], &client_data_hash[..]].concat();
+        Ok(pk.verify(&message, &self.signature)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::DeviceSignature;
+    use p256::ecdsa::SigningKey as P256SigningKey;
+    use signature::{DigestSigner, Error as SignatureError, Signer};
+
+    /// A passkey, as exercised through a browser.
+    struct Passkey(P256SigningKey);
+
+    impl Passkey {
+        fn assert(&self, challenge: &[u8], flags: u8) -> WebAuthnSignature {
+            let mut authenticator_data = [0u8; AUTHENTICATOR_DATA_MIN_LEN].to_vec();
+            authenticator_data[FLAGS_INDEX] = flags;
+            let client_data_json = [
+                CLIENT_DATA_PREFIX,
+                URL_SAFE_NO_PAD.encode(challenge).as_bytes(),
+                br#"","origin":"https://datalove.app","crossOrigin":false}"#,
+            ]
+            .concat();
+
+            let client_data_hash = Sha256::digest(&client_data_json);
+            let message = [&authenticator_data[..], &client_data_hash[..]].concat();
+            let signature = self.0.sign(&message);
+            WebAuthnSignature::new(authenticator_data, client_data_json, signature)
+        }
+    }
+
+    impl DigestSigner<Sha512, DeviceSignature> for Passkey {
+        fn try_sign_digest(&self, msg_digest: Sha512) -> Result<DeviceSignature, SignatureError> {
+            let sig = self.assert(&msg_digest.finalize(), USER_PRESENT);
+            Ok(DeviceSignature::WebAuthn(sig))
+        }
+    }
+
+    #[test]
+    fn can_verify_webauthn_signature() -> Result<(), Error> {
+        let passkey = Passkey(P256SigningKey::from_slice(&[1; 32]).unwrap());
+        let (device, _) = Device::init(*passkey.0.verifying_key(), &passkey)?;
+
+        let sig = device.sign_message::<Sha512, _>(b"hello world", &passkey)?;
+        device.verify(b"hello world", &sig)?;
+        assert!(device.verify(b"goodbye world", &sig).is_err());
+
+        // passkey must sign the message's challenge, with the user present
+        let challenge = WebAuthnSignature::challenge(b"hello world");
+        let sig = passkey.assert(&challenge, USER_PRESENT);
+        device.verify(b"hello world", &DeviceSignature::WebAuthn(sig))?;
+        let sig = passkey.assert(&challenge, 0);
+        assert!(device
+            .verify(b"hello world", &DeviceSignature::WebAuthn(sig))
+            .is_err());
+        let sig = passkey.assert(&challenge[..32], USER_PRESENT);
+        assert!(device
+            .verify(b"hello world", &DeviceSignature::WebAuthn(sig))
+            .is_err());
+
+        // signature must cover the client data
+        let mut sig = passkey.assert(&challenge, USER_PRESENT);
+        sig.client_data_json.pop();
+        assert!(device
+            .verify(b"hello world", &DeviceSignature::WebAuthn(sig.clone()))
+            .is_err());
+
+        let bytes = borsh::to_vec(&sig)?;
+        assert_eq!(WebAuthnSignature::try_from_slice(&bytes)?, sig);
+        Ok(())
+    }
+}
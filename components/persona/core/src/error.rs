@@ -13,9 +13,44 @@ pub enum Error {
     #[cfg_attr(feature = "std", error("Risc0 verification error: {0}"))]
     Risc0VerificationError(&'static str),
 
+    /// An error from the risc0 prover, which only runs on std hosts.
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "std", error("Risc0 prover error: {context}: {source}"))]
+    Risc0ProverError {
+        context: &'static str,
+        source: maybestd::boxed::Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    #[cfg_attr(
+        feature = "std",
+        error("journal image id {found:?} does not match {expected:?}")
+    )]
+    ImageIdMismatch {
+        expected: util::ImageId,
+        found: util::ImageId,
+    },
+
+    #[cfg_attr(feature = "std", error("Invalid journal: {0}"))]
+    InvalidJournal(&'static str),
+
+    #[cfg_attr(feature = "std", error("Invalid DID: {0}"))]
+    InvalidDid(&'static str),
+
+    #[cfg_attr(feature = "std", error("Invalid encoding: {0}"))]
+    InvalidEncoding(&'static str),
+
+    #[cfg_attr(feature = "std", error("invalid history at step {step}: {source}"))]
+    InvalidHistory {
+        step: usize,
+        source: maybestd::boxed::Box<Error>,
+    },
+
     #[cfg_attr(feature = "std", error("Invalid signature error: {0}"))]
     InvalidSignatureError(&'static str),
 
+    #[cfg_attr(feature = "std", error("Invalid merkle proof: {0}"))]
+    InvalidMerkleProof(&'static str),
+
     // #[cfg_attr(feature = "std", error("MerkleLog error: {0}"))]
     // MerkleLogError(#[cfg_attr(feature = "std", from)] merkle_log::Error),
     #[cfg_attr(feature = "std", error("invalid operation: {0}"))]
@@ -23,7 +58,10 @@ pub enum Error {
 
     // #[error("high threshold must be greater than low threshold")]
     // InvalidRelativeThresholds,
-    #[cfg_attr(feature = "std", error("total weight sum must be between 0 and 255"))]
+    #[cfg_attr(
+        feature = "std",
+        error("member weights must be non-zero and sum to at most 65535")
+    )]
     InvalidUserWeights,
 
     // #[error("high threshold must be greater than low threshold")]
@@ -35,10 +73,36 @@ pub enum Error {
     // InvalidGuardianCount,
     // #[error("guardian threshold sum must be greater than high threshold")]
     // InsufficientGuardianThreshold,
+    #[cfg_attr(
+        feature = "std",
+        error("insufficient signature weight: {weight} < {required}")
+    )]
+    InsufficientWeight {
+        weight: Threshold,
+        required: Threshold,
+    },
+
+    #[cfg_attr(feature = "std", error("persona is frozen"))]
+    Frozen,
+
     #[cfg_attr(feature = "std", error("unauthorized operation"))]
     Unauthorized,
 }
 
+#[cfg(feature = "std")]
+impl Error {
+    /// Wraps an error from the risc0 prover, noting what was being attempted.
+    pub fn risc0_prover(
+        context: &'static str,
+        source: impl Into<maybestd::boxed::Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        Self::Risc0ProverError {
+            context,
+            source: source.into(),
+        }
+    }
+}
+
 impl From<Error> for io::Error {
     fn from(e: Error) -> Self {
         Self::new(io::ErrorKind::Other, format!("{}", e))
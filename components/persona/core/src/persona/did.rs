@@ -0,0 +1,141 @@
+//! The `did:datalove` DID method.
+
+use super::Did;
+use crate::{
+    maybestd::{fmt, str::FromStr, string::String, vec::Vec},
+    util::{Empty, Sha256Digest, Sha256Pipe},
+    Error,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// A persona's [`Did`] as a W3C DID of the `datalove` method, i.e.
+/// `did:datalove:<multibase-encoded DID>`.
+///
+/// Displayed in base58btc, but parsed from any multibase encoding.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PersonaDid(Did);
+
+impl PersonaDid {
+    pub const METHOD: &'static str = "datalove";
+    const PREFIX: &'static str = "did:datalove:";
+
+    pub fn new(did: Did) -> Self {
+        Self(did)
+    }
+
+    pub fn did(&self) -> &Did {
+        &self.0
+    }
+}
+
+impl From<Did> for PersonaDid {
+    fn from(did: Did) -> Self {
+        Self(did)
+    }
+}
+
+impl From<PersonaDid> for Did {
+    fn from(did: PersonaDid) -> Self {
+        did.0
+    }
+}
+
+impl fmt::Display for PersonaDid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", Self::PREFIX, self.0)
+    }
+}
+
+impl FromStr for PersonaDid {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let id = s
+            .strip_prefix(Self::PREFIX)
+            .ok_or(Error::InvalidDid("expected a did:datalove DID"))?;
+        let did = id
+            .parse()
+            .map_err(|_| Error::InvalidDid("id must be a multibase-encoded digest"))?;
+        Ok(Self(did))
+    }
+}
+
+/// Disclosed persona metadata, whose digest a persona commits to as its
+/// `metadata`, from which DID documents are resolved.
+#[derive(Clone, Debug, Default, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DidMetadata {
+    services: Vec<DidService>,
+}
+
+impl DidMetadata {
+    pub fn new(services: Vec<DidService>) -> Self {
+        Self { services }
+    }
+
+    pub fn services(&self) -> &[DidService] {
+        &self.services
+    }
+
+    /// Digest of the metadata, to be committed to as a persona's `metadata`.
+    pub fn digest(&self) -> Sha256Digest {
+        Sha256Pipe::encode_to_writer(self, Empty)
+            .expect("should never fail to serialize and compute digest")
+            .into()
+    }
+}
+
+/// A service endpoint of a persona's DID document.
+#[derive(Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DidService {
+    /// Fragment identifying the service within the DID document.
+    id: String,
+    /// The service's type, e.g. `LinkedDomains`.
+    kind: String,
+    /// The service's endpoint URI.
+    endpoint: String,
+}
+
+impl DidService {
+    pub fn new(id: String, kind: String, endpoint: String) -> Self {
+        Self { id, kind, endpoint }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maybestd::string::ToString;
+    use multibase::Base;
+
+    #[test]
+    fn can_parse_did() -> Result<(), Error> {
+        let did = PersonaDid::new(Sha256Digest::from([7u32; 8]));
+        let s = did.to_string();
+        assert!(s.starts_with("did:datalove:z"));
+        assert_eq!(s.parse::<PersonaDid>()?, did);
+
+        // any multibase encoding of the digest is accepted
+        let base32 = multibase::encode(Base::Base32Lower, did.did().to_bytes());
+        let s = ["did:datalove:", &base32].concat();
+        assert_eq!(s.parse::<PersonaDid>()?, did);
+
+        assert!("did:key:z6Mk".parse::<PersonaDid>().is_err());
+        assert!("did:datalove:zabc".parse::<PersonaDid>().is_err());
+        assert!("did:datalove:!invalid".parse::<PersonaDid>().is_err());
+        Ok(())
+    }
+}
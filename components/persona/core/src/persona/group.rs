@@ -1,21 +1,22 @@
-use super::{Did, Weight};
+use super::{Did, Operation, Sign, Weight};
 use crate::{
-    device::{Device, DeviceSignature},
-    maybestd::{
-        cmp,
-        collections::BTreeMap,
-        io,
-        vec::{IntoIter, Vec},
+    device::{Device, DeviceBatch, DeviceLog, DeviceLogNode, DeviceSignature, LogExtension},
+    maybestd::{cmp, collections::BTreeMap, io, vec::Vec},
+    util::{
+        merkle::{self, MerkleProof},
+        risc0::{self, ImageId, Sha256Digest, TypedJournal},
+        Empty, Sha256Pipe,
     },
-    util::risc0::{Sha256Digest, TypedJournal},
     zksm::{ProverState, VerifierState},
     Error, Threshold,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
-use signature::Verifier;
+use sha2::Sha512;
+use signature::DigestSigner;
 
 /// Publicly committed state of the persona.
 #[derive(Clone, Debug, Default, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Persona {
     //// The DID of the persona.
     pub(super) did: Did,
@@ -27,24 +28,139 @@ pub struct Persona {
     /// usually a persona operation, but could be a message to just sign
     pub(super) msg: Sha256Digest,
 
-    // /// Digest of the group managing this persona.
-    // pub(super) group: Sha256Digest,
+    /// Digest of the transition that produced this state, binding the
+    /// persona's journal to it.
+    pub(super) op: Sha256Digest,
+
+    /// Verifier digest of the state machine this state was transitioned from,
+    /// i.e. the digest of the persona's previous journal.
+    pub(super) prev: Sha256Digest,
+
+    /// Merkle root of the members of the group managing this persona.
+    pub(super) group: Sha256Digest,
+
     /// The sequence number (ie. age by number of proofs generated).
     pub(super) seqno: u32,
+
+    /// Whether or not the persona is frozen.
+    pub(super) status: PersonaStatus,
     // ///
     // pub(super) clock: BloomClock<4, 96, Sha256>,
 }
 
+/// The status of a [`Persona`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[borsh(use_discriminant = true)]
+#[repr(u8)]
+pub enum PersonaStatus {
+    /// The persona can apply any operation.
+    #[default]
+    Active = 0,
+    /// The persona can only be thawed.
+    Frozen = 1,
+}
+
 impl Persona {
     pub const DEFAULT: Self = Self {
         did: Did::ZERO,
         msg: Sha256Digest::ZERO,
+        op: Sha256Digest::ZERO,
+        prev: Sha256Digest::ZERO,
         metadata: Sha256Digest::ZERO,
+        group: Sha256Digest::ZERO,
         seqno: 0,
+        status: PersonaStatus::Active,
     };
+
+    pub fn did(&self) -> &Did {
+        &self.did
+    }
+
+    pub fn metadata(&self) -> &Sha256Digest {
+        &self.metadata
+    }
+
+    pub fn msg(&self) -> &Sha256Digest {
+        &self.msg
+    }
+
+    pub fn op(&self) -> &Sha256Digest {
+        &self.op
+    }
+
+    pub fn prev(&self) -> &Sha256Digest {
+        &self.prev
+    }
+
+    pub fn group(&self) -> &Sha256Digest {
+        &self.group
+    }
+
+    pub fn seqno(&self) -> u32 {
+        self.seqno
+    }
+
+    pub fn status(&self) -> PersonaStatus {
+        self.status
+    }
+
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.status == PersonaStatus::Active
+    }
+
+    #[inline]
+    pub fn is_frozen(&self) -> bool {
+        self.status == PersonaStatus::Frozen
+    }
+
+    /// Digest of the persona's current public state.
+    pub fn head(&self) -> Sha256Digest {
+        Sha256Pipe::encode_to_writer(self, Empty)
+            .expect("should never fail to serialize and compute digest")
+            .into()
+    }
+
+    /// Asserts the member belongs to the group currently managing the persona,
+    /// without revealing any of the group's other members.
+    pub fn verify_member(&self, member: &Member, proof: &MerkleProof) -> Result<(), Error> {
+        proof.verify(&member.id(), Some(&member.digest()), &self.group)
+    }
+
+    /// Advances the sequence number, committing to the new metadata and message.
+    pub(super) fn advance(
+        &mut self,
+        metadata: Sha256Digest,
+        msg: Sha256Digest,
+    ) -> Result<(), Error> {
+        self.seqno = self
+            .seqno
+            .checked_add(1)
+            .ok_or_else(|| Error::InvalidOperation("persona seqno overflow"))?;
+        self.metadata = metadata;
+        self.msg = msg;
+        Ok(())
+    }
+
+    /// Advances the sequence number, retaining the current metadata and
+    /// updating the status.
+    pub(super) fn set_status(
+        &mut self,
+        status: PersonaStatus,
+        msg: Sha256Digest,
+    ) -> Result<(), Error> {
+        self.advance(self.metadata, msg)?;
+        self.status = status;
+        Ok(())
+    }
 }
 
-impl VerifierState for Persona {}
+impl VerifierState for Persona {
+    fn set_prev(&mut self, prev: &Sha256Digest) {
+        self.prev = *prev;
+    }
+}
 
 impl PartialOrd for Persona {
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
@@ -54,31 +170,59 @@ impl PartialOrd for Persona {
     }
 }
 
+/// A [`Persona`]'s proof journal, which doubles as a signature over the
+/// persona's committed `msg`.
 ///
-pub type PersonaSignature = TypedJournal<(Sha256Digest, Persona)>;
+/// Within the guest, the journal is verified (as an assumption of the current
+/// proof) upon deserialization, so the host must add the signing persona's
+/// receipt as an assumption to the executor environment.
+#[derive(Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PersonaSignature(
+    #[cfg_attr(
+        target_os = "zkvm",
+        borsh(deserialize_with = "TypedJournal::deserialize_verify_self")
+    )]
+    TypedJournal<(Sha256Digest, Persona)>,
+);
 
 impl PersonaSignature {
-    // (Self::Persona(member), MemberSignature::Persona(sig)) => {
-    //     // Persona proofs double as signatures, and are verified upon deserialization,
-    //     // so this just asserts that the proof belongs to this member and signs the same message.
-
-    //     let persona_sig = &sig.payload.as_inner().1;
-
-    //     if persona_sig.did != member.payload.did {
-    //         Err(Error::InvalidSignatureError(
-    //             "signature DID does not match member DID",
-    //         ))?;
-    //     }
-
-    //     if &persona_sig.msg != &member_op_digest {
-    //         Err(Error::InvalidSignatureError(
-    //             "signature message does not apply to operation",
-    //         ))?;
-    //     }
-    // }
-    // _ => Err(Error::InvalidSignatureError(
-    //     "signature / member type mismatch",
-    // ))?,
+    pub fn image_id(&self) -> &ImageId {
+        self.0.image_id()
+    }
+
+    /// The signing persona's public state.
+    pub fn persona(&self) -> &Persona {
+        &self.0.as_inner().1
+    }
+
+    /// Asserts the signature was produced by the persona with the given DID
+    /// by signing the given message (with a [`Sign`] operation).
+    ///
+    /// Assumes the journal has already been verified.
+    pub fn verify_message(&self, did: &Did, msg: &[u8]) -> Result<(), Error> {
+        let persona = self.persona();
+
+        if &persona.did != did {
+            Err(Error::InvalidSignatureError(
+                "signature DID does not match persona DID",
+            ))?;
+        }
+
+        if persona.msg != Sign::message_digest(msg) {
+            Err(Error::InvalidSignatureError(
+                "signature message does not match",
+            ))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<TypedJournal<(Sha256Digest, Persona)>> for PersonaSignature {
+    fn from(journal: TypedJournal<(Sha256Digest, Persona)>) -> Self {
+        Self(journal)
+    }
 }
 
 // pub struct PersonaState(TypedJournal<(Sha256Digest, Persona)>);
@@ -88,84 +232,112 @@ impl PersonaSignature {
 //
 
 /// Private state managed by and known only to the [`Persona`] and its [`Member`]s.
+///
+/// Commits to its members as the root of a sparse Merkle tree of
+/// [`Member::digest`]s keyed by [`Member::id`], so operations need only carry
+/// the members they touch (and their proofs) rather than the whole group.
 #[derive(Clone, Debug, Default, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Group {
-    // TODO: replace with merkle tree
-    members: Vec<Member>,
+    /// Merkle root of the group's members.
+    root: Sha256Digest,
+    /// The number of members in the group.
+    len: u32,
+    /// The total weight of the group's members.
+    weight: Threshold,
 }
 
 impl ProverState for Group {}
 
 impl Group {
     pub const DEFAULT: Self = Self {
-        members: Vec::new(),
+        root: Sha256Digest::ZERO,
+        len: 0,
+        weight: 0,
     };
 
-    // pub const HIGH_THRESHOLD: Threshold = u16::MAX >> 2; // 16383
-    // pub const MID_THRESHOLD: Threshold = u16::MAX >> 4; // 4095
-    // pub const LOW_THRESHOLD: Threshold = u16::MAX >> 8; // 255
+    /// Thresholds are fractions of the group's total weight, scaled to
+    /// [`Threshold::MAX`].
+    pub const HIGH_THRESHOLD: Threshold = u16::MAX / 3 * 2; // ~2/3
+    pub const MID_THRESHOLD: Threshold = u16::MAX >> 1; // ~1/2
+    pub const LOW_THRESHOLD: Threshold = u16::MAX >> 8; // any member
+
+    /// The maximum number of members in a group, which bounds the cost of
+    /// verifying a signature by all of its members.
+    pub const MAX_MEMBERS: usize = 1024;
+
+    /// Merkle root of the group's members.
+    pub fn root(&self) -> &Sha256Digest {
+        &self.root
+    }
 
     pub fn is_empty(&self) -> bool {
-        self.members.is_empty()
+        self.len == 0
     }
 
     pub fn len(&self) -> usize {
-        self.members.len()
+        self.len as usize
     }
 
-    /// Returns the weight of the group.
-    /// TODO: refactor this mechanism
-    // pub fn group_weight(&self) -> Threshold {
-    //     self.weights().fold(0u16, |a, b| a + b as Threshold)
-    // }
+    /// Returns the total weight of the group.
+    pub fn weight(&self) -> Threshold {
+        self.weight
+    }
 
-    // pub fn weights(&self) -> impl Iterator<Item = Weight> + '_ {
-    //     self.members.iter().map(|m| m.weight())
-    // }
+    /// Returns the minimum signature weight required to meet the given
+    /// threshold, which is always at least one.
+    pub fn required_weight(&self, threshold: Threshold) -> Threshold {
+        let max = Threshold::MAX as u32;
+        let required = (self.weight() as u32 * threshold as u32 + max - 1) / max;
+        cmp::max(required, 1) as Threshold
+    }
 
-    // pub fn devices(&self) -> impl Iterator<Item = Member> + '_ {
-    //     self.members.iter().filter_map(|m| match m {
-    //         Member::Device(d) => Some(m),
-    //         _ => None,
-    //     })
-    // }
+    /// Asserts the member belongs to the group.
+    pub fn verify_member(&self, member: &Member, proof: &MerkleProof) -> Result<(), Error> {
+        proof.verify(&member.id(), Some(&member.digest()), &self.root)
+    }
 
+    /// Produces a new group with the member updates applied, each of which
+    /// must be proven against the current group.
     ///
-    /// TODO: verify sig against group
-    pub fn add_signature(
-        &self,
-        idx: u8,
-        member_sig: MemberSignature,
-        group_sig: GroupSignature,
-    ) -> Result<GroupSignature, Error> {
-        // prune duplicate signer indices
-        let mut signers = self
-            .signer_iter(group_sig)
-            .map(|res| res.map(|signer| (signer.0, signer)))
-            .collect::<Result<BTreeMap<u8, _>, _>>()?;
+    /// Added members must have non-zero weights, and the resulting group's
+    /// weights must sum to a valid [`Threshold`].
+    pub(super) fn update(&self, updates: &[MemberUpdate]) -> Result<Self, Error> {
+        let mut leaves = Vec::with_capacity(updates.len());
+        let (mut removed_len, mut removed_weight) = (0u32, 0u32);
+        let (mut added_len, mut added_weight) = (0u32, 0u32);
+        for update in updates.iter() {
+            if let Some(member) = &update.old {
+                removed_len += 1;
+                removed_weight += member.weight() as u32;
+            }
+            if let Some(member) = &update.new {
+                if member.weight() == 0 {
+                    Err(Error::InvalidUserWeights)?;
+                }
+                added_len += 1;
+                added_weight += member.weight() as u32;
+            }
+
+            leaves.push(merkle::Update {
+                key: update.id()?,
+                old: update.old.as_ref().map(Member::digest),
+                new: update.new.as_ref().map(Member::digest),
+                proof: &update.proof,
+            });
+        }
 
-        // TODO: verify each current member_sig against stored member
-        let member = self
-            .members
-            .get(idx as usize)
-            .ok_or_else(|| Error::InvalidSignatureError("signature member index out of bounds"))?;
-
-        // add signer to group_sig at it appropriate index, erroring it already exists
-        signers
-            .insert(idx, (idx, member, member_sig))
-            .map_or(Ok(()), |_| {
-                Err(Error::InvalidSignatureError(
-                    "signature member index already signed",
-                ))
-            })?;
-
-        // aggregate indices and signatures
-        let indices = signers.values().fold(0u32, |i, (idx, _, _)| i | (1 << idx));
-        let signatures = signers.into_values().map(|(_, _, sig)| sig).collect();
-        Ok(GroupSignature {
-            indices,
-            signatures,
-        })
+        // removed members are proven present, so their len and weight cannot underflow
+        let root = merkle::update(&self.root, &mut leaves)?;
+        let len = (self.len - removed_len)
+            .checked_add(added_len)
+            .filter(|len| *len as usize <= Self::MAX_MEMBERS)
+            .ok_or(Error::InvalidOperation("group exceeds maximum members"))?;
+        let weight = (self.weight as u32 - removed_weight + added_weight)
+            .try_into()
+            .map_err(|_| Error::InvalidUserWeights)?;
+
+        Ok(Self { root, len, weight })
     }
 
     /// Verifies a group signature against the group's state.
@@ -174,6 +346,8 @@ impl Group {
         op_digest: &Sha256Digest,
         sig: &GroupSignature,
     ) -> Result<Threshold, Error> {
+        let mut cc = risc0::trace(format_args!("verifying group signature"), None);
+
         // assert participant count
         let num_participants = sig.len();
         if !(num_participants > 0 && num_participants <= self.len()) {
@@ -182,109 +356,462 @@ impl Group {
             ))?;
         }
 
+        // assert signers are unique (and canonically ordered)
+        if !sig
+            .signers
+            .windows(2)
+            .all(|w| w[0].member.id() < w[1].member.id())
+        {
+            Err(Error::InvalidSignatureError(
+                "signers must be unique and sorted by id",
+            ))?;
+        }
+
+        // verify membership of all signers at once
+        let mut leaves = sig
+            .signers
+            .iter()
+            .map(|signer| {
+                let digest = signer.member.digest();
+                merkle::Update {
+                    key: signer.member.id(),
+                    old: Some(digest),
+                    new: Some(digest),
+                    proof: &signer.proof,
+                }
+            })
+            .collect::<Vec<_>>();
+        merkle::update(&self.root, &mut leaves)
+            .map_err(|_| Error::InvalidSignatureError("signer is not a group member"))?;
+        cc = risc0::trace(
+            format_args!("verified membership of {} signers", num_participants),
+            Some(cc),
+        );
+
         let mut sig_weight: Threshold = 0;
 
-        // verify each member sig
-        // TODO: group and batch device sig verifies
-        while let Some((_, member, sig)) = self.signer_ref_iter(sig).next().transpose()? {
-            member.verify_signature(op_digest, &sig)?;
-            sig_weight += member.weight() as Threshold;
+        // verify each member sig, deferring device sigs to a single batch verification
+        let mut batch = DeviceBatch::with_capacity(num_participants);
+        for signer in sig.signers.iter() {
+            let (member, signature) = (&signer.member, &signer.signature);
+            let member_op_digest = member.member_op_digest(op_digest, signature)?;
+            match (member, signature) {
+                (Member::Device(device), MemberSignature::Device(extension)) => {
+                    // device must have appended the op to its log, extending its recorded head
+                    let entry = log_entry(&member_op_digest)?;
+                    let extension = &extension.payload;
+                    extension.verify(device.payload.head(), &entry)?;
+                    batch.push(
+                        &device.payload,
+                        &DeviceLogNode::message(extension.prev(), &entry),
+                        extension.signature(),
+                    )?;
+                }
+                _ => member.verify_signature(op_digest, signature)?,
+            }
+            sig_weight += signature.weight() as Threshold;
         }
+        batch.verify()?;
+        risc0::trace(
+            format_args!("verified {} signer signatures", num_participants),
+            Some(cc),
+        );
 
         Ok(sig_weight)
     }
+}
+
+/// A change to a single [`Group`] member, proven against the current group.
+///
+/// A member is added if it has no `old` value, removed if it has no `new`
+/// value, and replaced (with a member of the same id) otherwise.
+#[derive(Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemberUpdate {
+    old: Option<Member>,
+    new: Option<Member>,
+    proof: MerkleProof,
+}
+
+impl MemberUpdate {
+    /// Returns the id of the updated member.
+    pub fn id(&self) -> Result<[u8; 32], Error> {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) if old.id() == new.id() => Ok(old.id()),
+            (Some(member), None) | (None, Some(member)) => Ok(member.id()),
+            _ => Err(Error::InvalidOperation("invalid member update")),
+        }
+    }
+
+    /// Creates an update adding a member, given proof of its absence.
+    pub fn add(member: Member, proof: MerkleProof) -> Self {
+        Self {
+            old: None,
+            new: Some(member),
+            proof,
+        }
+    }
 
-    fn signer_iter(
+    /// Creates an update removing a member, given proof of its presence.
+    pub fn remove(member: Member, proof: MerkleProof) -> Self {
+        Self {
+            old: Some(member),
+            new: None,
+            proof,
+        }
+    }
+}
+
+/// The full set of a [`Group`]'s members, known to the host, from which the
+/// group, its membership proofs, and its signatures are produced.
+#[derive(Clone, Debug, Default, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+pub struct MemberSet {
+    members: BTreeMap<[u8; 32], Member>,
+}
+
+impl MemberSet {
+    /// Creates a new set of members, whose ids must be unique and whose
+    /// weights must be non-zero and sum to a valid [`Threshold`].
+    pub fn new(members: impl IntoIterator<Item = Member>) -> Result<Self, Error> {
+        let mut set = Self::default();
+        for member in members {
+            if set.members.insert(member.id(), member).is_some() {
+                Err(Error::InvalidOperation(
+                    "group contains duplicate member ids",
+                ))?;
+            }
+        }
+
+        set.validate()?;
+        Ok(set)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn get(&self, id: &[u8; 32]) -> Option<&Member> {
+        self.members.get(id)
+    }
+
+    pub fn contains(&self, id: &[u8; 32]) -> bool {
+        self.members.contains_key(id)
+    }
+
+    /// Returns an iterator over the members, sorted by id.
+    pub fn iter(&self) -> impl Iterator<Item = &Member> {
+        self.members.values()
+    }
+
+    /// Computes the [`Group`] committing to these members.
+    pub fn group(&self) -> Group {
+        Group {
+            root: merkle::root(&self.leaves()),
+            len: self.members.len() as u32,
+            weight: self.weight(),
+        }
+    }
+
+    /// Produces a proof of the presence (or absence) of a member with the
+    /// given id.
+    pub fn proof(&self, id: &[u8; 32]) -> MerkleProof {
+        merkle::proof(&self.leaves(), id)
+    }
+
+    /// Produces a new set with `to_remove` members removed and `to_add`
+    /// members added, along with the updates proving the swap against the
+    /// current [`Group`].
+    ///
+    /// Removing and adding members with the same id replaces the member.
+    pub fn swap(
         &self,
-        sig: GroupSignature,
-    ) -> impl Iterator<Item = Result<(u8, &Member, MemberSignature), Error>> + '_ {
-        sig.into_iter().map(move |(idx, sig)| {
-            let member = self.members.get(idx as usize).ok_or_else(|| {
-                Error::InvalidSignatureError("signature member index out of bounds")
-            })?;
-            Ok((idx, member, sig))
-        })
+        to_remove: &[Member],
+        to_add: &[Member],
+    ) -> Result<(Self, Vec<MemberUpdate>), Error> {
+        let leaves = self.leaves();
+        let mut swapped = self.clone();
+        let mut updates = BTreeMap::<[u8; 32], MemberUpdate>::new();
+
+        for member in to_remove {
+            let id = member.id();
+            if swapped.members.get(&id) != Some(member) {
+                Err(Error::InvalidOperation("member to remove is not in group"))?;
+            }
+
+            swapped.members.remove(&id);
+            updates.insert(
+                id,
+                MemberUpdate {
+                    old: Some(member.clone()),
+                    new: None,
+                    proof: merkle::proof(&leaves, &id),
+                },
+            );
+        }
+
+        for member in to_add {
+            let id = member.id();
+            if swapped.members.insert(id, member.clone()).is_some() {
+                Err(Error::InvalidOperation("member to add is already in group"))?;
+            }
+
+            updates
+                .entry(id)
+                .and_modify(|update| update.new = Some(member.clone()))
+                .or_insert_with(|| MemberUpdate {
+                    old: None,
+                    new: Some(member.clone()),
+                    proof: merkle::proof(&leaves, &id),
+                });
+        }
+
+        swapped.validate()?;
+        Ok((swapped, updates.into_values().collect()))
     }
 
-    fn signer_ref_iter<'a: 'b, 'b>(
-        &'a self,
-        sig: &'b GroupSignature,
-    ) -> impl Iterator<Item = Result<(u8, &'a Member, &'b MemberSignature), Error>> + 'b {
-        sig.iter().map(move |(idx, sig)| {
-            let member = self.members.get(idx as usize).ok_or_else(|| {
-                Error::InvalidSignatureError("signature member index out of bounds")
-            })?;
-            Ok((idx, member, sig))
-        })
+    /// Produces a new set with the log heads of the signature's signing
+    /// devices advanced, as recorded by the [`Group`] upon applying the
+    /// signed operation.
+    ///
+    /// Devices updated by the operation itself are left untouched.
+    pub fn advance(&self, op: &Operation, signature: &GroupSignature) -> Result<Self, Error> {
+        let mut advanced = self.clone();
+        for update in signature.advances(&op.member_ids()?) {
+            let id = update.id()?;
+            if let (Some(member), Some(new)) = (advanced.members.get_mut(&id), update.new) {
+                *member = new;
+            }
+        }
+        Ok(advanced)
+    }
+
+    /// Adds a member's signature to a group signature, erroring if the
+    /// member is not in the group or has already signed.
+    pub fn add_signature(
+        &self,
+        id: &[u8; 32],
+        member_sig: MemberSignature,
+        mut group_sig: GroupSignature,
+    ) -> Result<GroupSignature, Error> {
+        let member = self
+            .get(id)
+            .ok_or_else(|| Error::InvalidSignatureError("signer is not a group member"))?;
+
+        // insert signer sorted by id, erroring if it already exists
+        let idx = match group_sig
+            .signers
+            .binary_search_by_key(id, |signer| signer.member.id())
+        {
+            Ok(_) => return Err(Error::InvalidSignatureError("member has already signed")),
+            Err(idx) => idx,
+        };
+        group_sig.signers.insert(
+            idx,
+            Signer {
+                member: member.clone(),
+                proof: self.proof(id),
+                signature: member_sig,
+            },
+        );
+
+        Ok(group_sig)
+    }
+
+    fn weight(&self) -> Threshold {
+        self.members
+            .values()
+            .fold(0, |a, m| a.saturating_add(m.weight() as Threshold))
+    }
+
+    fn leaves(&self) -> Vec<(merkle::Key, Sha256Digest)> {
+        self.members
+            .iter()
+            .map(|(id, member)| (*id, member.digest()))
+            .collect()
+    }
+
+    /// Asserts the set does not exceed [`Group::MAX_MEMBERS`], and that
+    /// member weights are non-zero and sum to a valid [`Threshold`].
+    fn validate(&self) -> Result<(), Error> {
+        if self.len() > Group::MAX_MEMBERS {
+            Err(Error::InvalidOperation("group exceeds maximum members"))?;
+        }
+
+        self.members
+            .values()
+            .try_fold(0, |weight: Threshold, member| match member.weight() {
+                0 => None,
+                w => weight.checked_add(w as Threshold),
+            })
+            .map(|_| ())
+            .ok_or(Error::InvalidUserWeights)
     }
 }
 
+/// The signatures of a subset of a [`Group`]'s members, along with proofs of
+/// their membership.
 ///
-#[derive(Clone, Debug, Default, Eq, PartialEq, BorshSerialize)]
+/// Encoded with a leading [`GroupSignature::VERSION`] byte.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct GroupSignature {
-    indices: u32,
-    signatures: Vec<MemberSignature>,
+    /// Signers, sorted by member id.
+    signers: Vec<Signer>,
 }
 
 impl GroupSignature {
+    /// The version of the signature's encoding:
+    /// - `1`: (unsupported) signers sorted by member id, each with its proof of
+    ///   membership, with Ed25519 devices signing with Ed25519ph
+    /// - `2`: (unsupported) as `1`, with device signers' extensions of their logs
+    /// - `3`: as `2`, with Ed25519 devices signing with pure Ed25519 (see
+    ///   [`Device::SIGNING_CONTEXT`]), as Ed25519ph signatures no longer verify
+    ///
+    /// The original, unversioned encoding (a `u32` bitmask of signing member
+    /// indices) is unreadable: its leading byte may collide with a version, so
+    /// such signatures cannot be distinguished and must be re-signed.
+    pub const VERSION: u8 = 3;
+
+    pub fn is_empty(&self) -> bool {
+        self.signers.is_empty()
+    }
+
     pub fn len(&self) -> usize {
-        self.signatures.len()
+        self.signers.len()
     }
 
-    fn indices_iter(&self) -> impl Iterator<Item = u8> {
-        let indices = self.indices;
-        (0..32u8)
-            .into_iter()
-            .filter_map(move |idx| ((indices & (1 << idx)) != 0).then(|| idx))
+    /// Returns the ids of the signing members.
+    pub fn signer_ids(&self) -> impl Iterator<Item = [u8; 32]> + '_ {
+        self.signers.iter().map(|signer| signer.member.id())
     }
 
-    /// Returns an iterator over the member's group index and its signature.
-    fn iter(&self) -> impl Iterator<Item = (u8, &MemberSignature)> {
-        self.indices_iter().zip(self.signatures.iter())
+    /// Updates advancing the recorded log heads of the signing devices,
+    /// besides those with the given ids (i.e. those updated by the signed
+    /// operation itself).
+    ///
+    /// Assumes the signature has already been verified.
+    pub(super) fn advances(&self, skip: &[[u8; 32]]) -> Vec<MemberUpdate> {
+        self.signers
+            .iter()
+            .filter_map(|signer| match (&signer.member, &signer.signature) {
+                (Member::Device(device), MemberSignature::Device(extension))
+                    if !skip.contains(&device.payload.id()) =>
+                {
+                    let mut advanced = device.clone();
+                    advanced.payload.set_head(*extension.payload.head());
+                    Some(MemberUpdate {
+                        old: Some(signer.member.clone()),
+                        new: Some(Member::Device(advanced)),
+                        proof: signer.proof.clone(),
+                    })
+                }
+                _ => None,
+            })
+            .collect()
     }
+}
 
-    fn into_iter(self) -> impl Iterator<Item = (u8, MemberSignature)> {
-        self.indices_iter().zip(self.signatures.into_iter())
+impl BorshSerialize for GroupSignature {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        Self::VERSION.serialize(writer)?;
+        self.signers.serialize(writer)
     }
 }
 
 impl BorshDeserialize for GroupSignature {
     fn deserialize_reader<R: io::Read>(reader: &mut R) -> Result<Self, io::Error> {
-        let indices = u32::deserialize_reader(reader)?;
-        let signatures = Vec::deserialize_reader(reader)?;
-        if indices.count_ones() as usize != signatures.len() {
+        if u8::deserialize_reader(reader)? != Self::VERSION {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
-                "mismatch between num indices and signatures",
+                "unsupported group signature version",
             ));
         }
 
-        Ok(Self {
-            indices,
-            signatures,
-        })
+        let len = u32::deserialize_reader(reader)? as usize;
+        if len > Group::MAX_MEMBERS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "group signature exceeds maximum members",
+            ));
+        }
+
+        let signers = (0..len)
+            .map(|_| Signer::deserialize_reader(reader))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { signers })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for GroupSignature {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error as _;
+
+        #[derive(serde::Deserialize)]
+        struct Signature {
+            signers: Vec<Signer>,
+        }
+
+        let Signature { signers } = Signature::deserialize(deserializer)?;
+        if signers.len() > Group::MAX_MEMBERS {
+            return Err(D::Error::custom("group signature exceeds maximum members"));
+        }
+        Ok(Self { signers })
     }
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Signer {
+    member: Member,
+    proof: MerkleProof,
+    signature: MemberSignature,
+}
+
 //
 // Member
 //
 
 /// A [`Persona`] is managed by members, each of which is either a device or another persona.
+///
+/// Persona members are recorded by their [`Did`] alone, so that they remain
+/// members as they advance; their state is instead proven by the journal
+/// accompanying each of their signatures.
 #[derive(Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[borsh(use_discriminant = true)]
 #[non_exhaustive]
 // #[repr(align(4))]
 pub enum Member {
     Device(MemberInner<Device>),
-    // Persona(MemberInner<Persona>),
+    Persona(MemberInner<Did>),
 }
 
 impl Member {
+    /// Creates a new device member with the given weight.
+    pub fn device(weight: Weight, device: Device) -> Self {
+        Self::Device(MemberInner {
+            weight,
+            payload: device,
+        })
+    }
+
+    /// Creates a new persona member with the given DID and weight.
+    pub fn persona(weight: Weight, did: Did) -> Self {
+        Self::Persona(MemberInner {
+            weight,
+            payload: did,
+        })
+    }
+
     pub fn id(&self) -> [u8; 32] {
         match self {
             Self::Device(member) => member.payload.id(),
-            // Self::Persona(member) => member.payload.id(),
+            Self::Persona(member) => member.payload.to_bytes(),
         }
     }
 
@@ -292,23 +819,42 @@ impl Member {
     pub const fn weight(&self) -> Weight {
         match self {
             Self::Device(member) => member.weight,
-            // Self::Persona(member) => member.weight,
+            Self::Persona(member) => member.weight,
         }
     }
+
+    /// Digest of the member, committed to the [`Group`]'s Merkle tree.
+    pub fn digest(&self) -> Sha256Digest {
+        Sha256Pipe::encode_to_writer(self, Empty)
+            .expect("should never fail to serialize and compute digest")
+            .into()
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MemberInner<T> {
     weight: Weight,
     payload: T,
 }
 
+impl<T> MemberInner<T> {
+    pub const fn weight(&self) -> Weight {
+        self.weight
+    }
+
+    pub fn payload(&self) -> &T {
+        &self.payload
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[borsh(use_discriminant = true)]
 #[non_exhaustive]
 pub enum MemberSignature {
-    Device(MemberInner<DeviceSignature>),
-    // Persona(MemberInner<PersonaSignature>),
+    Device(MemberInner<LogExtension>),
+    Persona(MemberInner<PersonaSignature>),
 }
 
 impl MemberSignature {
@@ -316,54 +862,105 @@ impl MemberSignature {
     pub const fn weight(&self) -> Weight {
         match self {
             Self::Device(member) => member.weight,
-            // Self::Persona(member) => member.weight,
+            Self::Persona(member) => member.weight,
         }
     }
 }
 
+impl MemberSignature {
+    /// Creates a new device member signature with the given weight, from the
+    /// device's extension of its log by the signed entry.
+    pub fn device(weight: Weight, extension: LogExtension) -> Self {
+        Self::Device(MemberInner {
+            weight,
+            payload: extension,
+        })
+    }
+
+    /// Creates a new persona member signature with the given weight.
+    pub fn persona(weight: Weight, signature: PersonaSignature) -> Self {
+        Self::Persona(MemberInner {
+            weight,
+            payload: signature,
+        })
+    }
+}
+
 impl Member {
+    /// Signs an operation digest with the member's full weight, appending it
+    /// to the device's log, which must extend the member's recorded head.
+    pub fn sign<Si>(
+        &self,
+        op_digest: &Sha256Digest,
+        signer: &Si,
+        log: &mut DeviceLog,
+    ) -> Result<MemberSignature, Error>
+    where
+        Si: DigestSigner<Sha512, DeviceSignature>,
+    {
+        let member_op_digest = *op_digest ^ self.weight() as u32;
+
+        match self {
+            Self::Device(member) => {
+                let known = *member.payload.head();
+                let mut device = member.payload.clone();
+                device.fast_forward(log.head(), &log.consistency_proof(known.len())?)?;
+                device.append(&member_op_digest, signer, log)?;
+                Ok(MemberSignature::device(
+                    member.weight,
+                    log.extension(&known)?,
+                ))
+            }
+            Self::Persona(_) => Err(Error::InvalidSignatureError(
+                "persona members sign with their own proofs",
+            )),
+        }
+    }
+
     pub fn verify_signature(
         &self,
         op_digest: &Sha256Digest,
         signature: &MemberSignature,
     ) -> Result<(), Error> {
-        // assert signer's weight is lte member weight
-        if !(signature.weight() <= self.weight()) {
-            Err(Error::InvalidSignatureError(
-                "signature weight exceeds member weight",
-            ))?;
-        }
-
-        // create modified op digest, reflecting signer's weight for this operation
-        let member_op_digest = { *op_digest ^ signature.weight() as u32 };
+        let member_op_digest = self.member_op_digest(op_digest, signature)?;
 
         match (self, signature) {
-            (Self::Device(member), MemberSignature::Device(sig)) => member
+            (Self::Device(member), MemberSignature::Device(extension)) => member
                 .payload
-                .verify(member_op_digest.as_ref(), &sig.payload)?,
-            // (Self::Persona(member), MemberSignature::Persona(sig)) => {
-            //     // Persona proofs double as signatures, and are verified upon deserialization,
-            //     // so this just asserts that the proof belongs to this member and signs the same message.
-
-            //     let persona_sig = &sig.payload.as_inner().1;
-
-            //     if persona_sig.did != member.payload.did {
-            //         Err(Error::InvalidSignatureError(
-            //             "signature DID does not match member DID",
-            //         ))?;
-            //     }
-
-            //     if &persona_sig.msg != &member_op_digest {
-            //         Err(Error::InvalidSignatureError(
-            //             "signature message does not apply to operation",
-            //         ))?;
-            //     }
-            // }
-            // _ => Err(Error::InvalidSignatureError(
-            //     "signature / member type mismatch",
-            // ))?,
+                .verify_log_extension(&log_entry(&member_op_digest)?, &extension.payload)?,
+            (Self::Persona(member), MemberSignature::Persona(sig)) => {
+                // Persona proofs double as signatures, and are verified upon deserialization,
+                // so this just asserts that the proof belongs to this member and signs the same message.
+                sig.payload
+                    .verify_message(&member.payload, member_op_digest.as_ref())?
+            }
+            _ => Err(Error::InvalidSignatureError(
+                "signature / member type mismatch",
+            ))?,
         };
 
         Ok(())
     }
+
+    /// Asserts the signature's weight does not exceed the member's, returning
+    /// the op digest signed by the member (reflecting the signer's weight for
+    /// this operation).
+    fn member_op_digest(
+        &self,
+        op_digest: &Sha256Digest,
+        signature: &MemberSignature,
+    ) -> Result<Sha256Digest, Error> {
+        if signature.weight() > self.weight() {
+            Err(Error::InvalidSignatureError(
+                "signature weight exceeds member weight",
+            ))?;
+        }
+
+        Ok(*op_digest ^ signature.weight() as u32)
+    }
+}
+
+/// Digest of a member op digest, as appended to a device's log.
+fn log_entry(member_op_digest: &Sha256Digest) -> Result<Sha256Digest, Error> {
+    Ok(Sha256Pipe::encode_to_writer(member_op_digest, Empty)?.into())
 }
@@ -1,8 +1,13 @@
+mod did;
 mod group;
 mod ops;
 
-pub use group::{Group, GroupSignature, Member, MemberSignature, Persona};
-pub use ops::SignedOperation;
+pub use did::{DidMetadata, DidService, PersonaDid};
+pub use group::{
+    Group, GroupSignature, Member, MemberSet, MemberSignature, MemberUpdate, Persona,
+    PersonaSignature, PersonaStatus,
+};
+pub use ops::{Bump, Init, Operation, Rotate, Sign, SignPayload, SignedOperation, Swap};
 
 use crate::util::Sha256Digest;
 
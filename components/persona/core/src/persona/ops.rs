@@ -1,6 +1,8 @@
-use super::{Group, Member, Persona, Threshold, Weight};
+use super::{Group, Member, MemberSet, MemberUpdate, Persona, PersonaStatus, Threshold, Weight};
 use crate::{
-    util::{risc0::Sha256, Sha256Digest},
+    device::{Device, DeviceSignature},
+    maybestd::vec::Vec,
+    util::{merkle::MerkleProof, risc0::Sha256, Empty, Sha256Digest, Sha256Pipe},
     zksm::Operation as IOperation,
     Error, GroupSignature,
 };
@@ -9,6 +11,7 @@ use digest::Digest;
 
 ///
 #[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SignedOperation {
     /// The operation payload.
     op: Operation,
@@ -20,55 +23,58 @@ pub struct SignedOperation {
 }
 
 impl SignedOperation {
-    // /// Verifies a group signature against the group's state.
-    // pub fn verify_signature(
-    //     &self,
-    //     op_digest: &Sha256Digest,
-    //     sig: &GroupSignature,
-    // ) -> Result<Threshold, Error> {
-    //     // assert participant count
-    //     let num_participants = sig.len();
-    //     if !(num_participants > 0 && num_participants <= self.len()) {
-    //         return Err(signature::Error::new().into());
-    //     }
-
-    //     let mut sig_weight = 0u16;
+    pub fn new(op: Operation, signature: GroupSignature) -> Self {
+        Self { op, signature }
+    }
 
-    //     // verify each member sig
-    //     // TODO: group and batch device sig verifies
-    //     for (member_idx, member_sig) in sig.idx_iter() {
-    //         let member = &self.members[member_idx];
-    //         member.verify_signature(op_digest, &member_sig)?;
+    /// Computes the digest of an [`Operation`] to be signed by the group.
+    ///
+    /// Commits to the persona's current public state, so signatures cannot be
+    /// replayed against other personas or other points in a persona's history.
+    pub fn op_digest(op: &Operation, persona: &Persona) -> Sha256Digest {
+        Sha256Pipe::encode_to_writer(&(persona, op), Empty)
+            .expect("should never fail to serialize and compute digest")
+            .into()
+    }
 
-    //         sig_weight += member.weight() as Threshold;
-    //     }
+    pub fn as_op(&self) -> &Operation {
+        &self.op
+    }
 
-    //     Ok(sig_weight)
-    // }
+    pub fn signature(&self) -> &GroupSignature {
+        &self.signature
+    }
 }
 
 impl IOperation<Group, Persona> for SignedOperation {
     #[inline]
     fn validate(
         &self,
-        op_digest: &Sha256Digest,
+        self_digest: &Sha256Digest,
         persona: &Persona,
         group: &Group,
     ) -> Result<(), Error> {
-        let sig_group = if group.len() == 0 {
-            self.op
+        let init_group;
+        let sig_group = if group.is_empty() {
+            init_group = self
+                .op
                 .try_as_init()
                 .ok_or_else(|| {
                     Error::InvalidOperation("only Init can be applied to an empty group")
                 })?
-                .as_ref()
+                .group()?;
+            &init_group
         } else {
             group
         };
 
-        let sig_weight = sig_group.verify_signature(op_digest, &self.signature)?;
-        // self.operation.verify_weight(sig_weight, sig_group)?;
-        self.op.validate(op_digest, persona, group)?;
+        let op_digest = Self::op_digest(&self.op, persona);
+        let sig_weight = sig_group.verify_signature(&op_digest, &self.signature)?;
+        match &self.op {
+            Operation::Rotate(op) => op.verify_signers(&self.signature)?,
+            op => op.verify_weight(sig_weight, sig_group)?,
+        };
+        self.op.validate(self_digest, persona, group)?;
 
         Ok(())
     }
@@ -80,22 +86,45 @@ impl IOperation<Group, Persona> for SignedOperation {
         persona: &mut Persona,
         group: &mut Group,
     ) -> Result<(), Error> {
-        todo!()
+        // group signature has already been verified by `validate`, so advance
+        // the recorded log heads of signing devices untouched by the op
+        let advances = self.signature.advances(&self.op.member_ids()?);
+        match self.op {
+            Operation::Swap(op) => op.apply_with(self_digest, persona, group, advances)?,
+            Operation::Rotate(op) => op.apply_with(self_digest, persona, group, advances)?,
+            op => {
+                op.apply(self_digest, persona, group)?;
+                *group = group.update(&advances)?;
+                persona.group = *group.root();
+            }
+        }
+
+        // commit to the transition, as not every op commits to it in `msg`
+        persona.op = self_digest;
+        Ok(())
     }
 }
 
 /// An operation to be applied to a persona's [`State`].
 #[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[borsh(use_discriminant = true)]
 #[non_exhaustive]
 pub enum Operation {
     /// The initialization operation, that creates a new [`Persona`] from the provided [`MemberState`].
     Init(init::Init),
-    // Bump(bump::Bump),
-    // Swap(swap::Swap),
-    // Sign(sign::Sign),
-    // Freeze,
-    // Thaw,
+    /// The bump operation, which only advances the [`Persona`]'s sequence number.
+    Bump(bump::Bump),
+    /// The member swap operation, which adds, removes or replaces members of the [`Persona`].
+    Swap(swap::Swap),
+    /// The sign operation, which produces a signature on behalf of the [`Persona`].
+    Sign(#[cfg_attr(feature = "serde", serde(with = "sign::serde_sign"))] sign::Sign),
+    /// Freezes the [`Persona`], blocking all operations besides [`Operation::Thaw`].
+    Freeze,
+    /// Thaws a frozen [`Persona`].
+    Thaw,
+    /// The (device) key rotation operation, which replaces a single device's key.
+    Rotate(rotate::Rotate),
 }
 
 impl Operation {
@@ -105,9 +134,46 @@ impl Operation {
             _ => None,
         }
     }
+
+    /// Returns the ids of the [`Group`] members updated by the operation.
+    pub(super) fn member_ids(&self) -> Result<Vec<[u8; 32]>, Error> {
+        match self {
+            Self::Swap(op) => op.member_ids(),
+            Self::Rotate(op) => op.member_ids(),
+            _ => Ok(Vec::new()),
+        }
+    }
 }
 
-impl Operation {}
+impl Operation {
+    /// Returns the [`Group`] threshold required to authorize the operation.
+    pub const fn threshold(&self) -> Threshold {
+        match self {
+            Self::Init(_) => Group::HIGH_THRESHOLD,
+            Self::Bump(_) => Group::LOW_THRESHOLD,
+            Self::Swap(_) => Group::HIGH_THRESHOLD,
+            Self::Sign(_) => Group::MID_THRESHOLD,
+            Self::Freeze => Group::MID_THRESHOLD,
+            Self::Thaw => Group::HIGH_THRESHOLD,
+            // rotations are authorized by the rotated device alone
+            Self::Rotate(_) => Group::LOW_THRESHOLD,
+        }
+    }
+
+    /// Asserts the signature weight meets the operation's threshold within
+    /// the signing group.
+    pub fn verify_weight(&self, sig_weight: Threshold, group: &Group) -> Result<(), Error> {
+        let required = group.required_weight(self.threshold());
+        if sig_weight < required {
+            return Err(Error::InsufficientWeight {
+                weight: sig_weight,
+                required,
+            });
+        }
+
+        Ok(())
+    }
+}
 
 impl IOperation<Group, Persona> for Operation {
     #[inline]
@@ -117,13 +183,18 @@ impl IOperation<Group, Persona> for Operation {
         persona: &Persona,
         group: &Group,
     ) -> Result<(), Error> {
+        // frozen personas can only be thawed
+        if persona.is_frozen() && !matches!(self, Self::Thaw) {
+            return Err(Error::Frozen);
+        }
+
         match self {
             // group weight must be greater than or equal to the signature weight
             Self::Init(op) => op.validate(self_digest, persona, group),
             // use case(s):
             //  - bump: only updates seqno + group member clocks (no threshold change)
             //      - can be self-signed (i.e. by only members being updated)
-            // Self::Bump(op)
+            Self::Bump(op) => op.validate(self_digest, persona, group),
 
             // use case(s):
             //  - addition:
@@ -132,12 +203,27 @@ impl IOperation<Group, Persona> for Operation {
             //      - self-solo-signed iff 1:1 swap && old signs new(head), no threshold change
             //  - ???
             // CANNOT be ONLY self-signed personas
-            // Self::Swap(op) => op.validate(self_digest, persona, group),
-
+            Self::Swap(op) => op.validate(self_digest, persona, group),
             // use case(s):
             //  - sign: ... ? something that requires consensus?
             // CANNOT be ONLY self-signed personas
-            // Self::Sign(op) => op.validate(self_digest, persona, group),
+            Self::Sign(op) => op.validate(self_digest, persona, group),
+
+            // use case(s):
+            //  - freeze: stops a (possibly compromised) persona from operating, pending a swap
+            Self::Freeze if persona == &Persona::DEFAULT || group.is_empty() => {
+                Err(Error::InvalidOperation("persona not initialized"))
+            }
+            Self::Freeze => Ok(()),
+            Self::Thaw if !persona.is_frozen() => {
+                Err(Error::InvalidOperation("persona is not frozen"))
+            }
+            Self::Thaw => Ok(()),
+
+            // use case(s):
+            //  - (device) key rotation: 1:1 swap && old signs new(head), no threshold change
+            //      - self-solo-signed iff 1:1 swap && old signs new(head), no threshold change
+            Self::Rotate(op) => op.validate(self_digest, persona, group),
         }
     }
 
@@ -150,14 +236,19 @@ impl IOperation<Group, Persona> for Operation {
     ) -> Result<(), Error> {
         match self {
             Self::Init(op) => op.apply(self_digest, persona, group),
-            // Self::Swap(op) => op.apply(self_digest, persona, group),
-            // Self::Sign(op) => op.apply(self_digest, persona, group),
+            Self::Bump(op) => op.apply(self_digest, persona, group),
+            Self::Swap(op) => op.apply(self_digest, persona, group),
+            Self::Sign(op) => op.apply(self_digest, persona, group),
+            Self::Freeze => persona.set_status(PersonaStatus::Frozen, self_digest),
+            Self::Thaw => persona.set_status(PersonaStatus::Active, self_digest),
+            Self::Rotate(op) => op.apply(self_digest, persona, group),
         }
     }
 }
 
 /// All operations besides must carry additional info to be verifiably applied.
 #[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(align(4))]
 pub struct GenericOperation<T> {
     /// The new metadata to be associated with the [`Persona`].
@@ -188,6 +279,12 @@ where
     }
 }
 
+pub use bump::Bump;
+pub use init::Init;
+pub use rotate::Rotate;
+pub use sign::{Sign, SignPayload};
+pub use swap::Swap;
+
 mod init {
     use super::*;
 
@@ -195,23 +292,30 @@ mod init {
     pub type Init = GenericOperation<InitInner>;
 
     #[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct InitInner {
         // msg: Sha256Digest,
-        group: Group,
+        members: Vec<Member>,
     }
 
     impl Init {
-        pub fn new(metadata: Sha256Digest, group: Group) -> Self {
+        pub fn new(metadata: Sha256Digest, members: &MemberSet) -> Self {
             Self {
                 new_metadata: metadata,
-                payload: InitInner { group },
+                payload: InitInner {
+                    members: members.iter().cloned().collect(),
+                },
             }
         }
-    }
 
-    impl AsRef<Group> for InitInner {
-        fn as_ref(&self) -> &Group {
-            &self.group
+        /// Computes the initial [`Group`] from its members.
+        pub fn group(&self) -> Result<Group, Error> {
+            if self.payload.members.len() > Group::MAX_MEMBERS {
+                return Err(Error::InvalidOperation("group exceeds maximum members"));
+            }
+
+            let members = MemberSet::new(self.payload.members.iter().cloned())?;
+            Ok(members.group())
         }
     }
 
@@ -233,6 +337,12 @@ mod init {
                 return Err(Error::InvalidOperation("persona already initialized"));
             }
 
+            if self.group()?.is_empty() {
+                return Err(Error::InvalidOperation(
+                    "group must have at least one member",
+                ));
+            }
+
             Ok(())
         }
 
@@ -244,7 +354,8 @@ mod init {
         ) -> Result<(), Error> {
             // let Self { payload, .. } = self;
 
-            *group = self.payload.group;
+            *group = self.group()?;
+            persona.group = *group.root();
             persona.seqno = 1;
             persona.metadata = self.new_metadata;
             // persona.msg = self.payload.msg;
@@ -264,9 +375,52 @@ mod bump {
     use super::*;
 
     /// The bump operation, which increments the [`Persona`]'s sequence number.
-    pub type Bump = GenericOperation<BumpInner>;
+    ///
+    /// Serves as a cheap liveness proof (and optional metadata refresh) that
+    /// leaves the [`Group`] untouched.
+    #[derive(Clone, Debug, Default, BorshDeserialize, BorshSerialize)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct Bump {
+        /// The new metadata to be associated with the [`Persona`], or `None`
+        /// to retain the current metadata.
+        new_metadata: Option<Sha256Digest>,
+    }
+
+    impl Bump {
+        pub fn new(metadata: Option<Sha256Digest>) -> Self {
+            Self {
+                new_metadata: metadata,
+            }
+        }
+    }
 
-    pub struct BumpInner {}
+    impl IOperation<Group, Persona> for Bump {
+        fn validate(
+            &self,
+            _self_digest: &Sha256Digest,
+            persona: &Persona,
+            group: &Group,
+        ) -> Result<(), Error> {
+            // state should be initialized
+            if persona == &Persona::DEFAULT || group.is_empty() {
+                return Err(Error::InvalidOperation("persona not initialized"));
+            }
+
+            Ok(())
+        }
+
+        fn apply(
+            self,
+            self_digest: Sha256Digest,
+            persona: &mut Persona,
+            _group: &mut Group,
+        ) -> Result<(), Error> {
+            let metadata = self.new_metadata.unwrap_or(persona.metadata);
+            persona.advance(metadata, self_digest)?;
+
+            Ok(())
+        }
+    }
 }
 
 mod swap {
@@ -276,64 +430,343 @@ mod swap {
     pub type Swap = GenericOperation<SwapInner>;
 
     #[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct SwapInner {
-        members_to_remove: Vec<Member>,
-        members_to_add: Vec<Member>,
+        updates: Vec<MemberUpdate>,
+    }
+
+    impl Swap {
+        /// Creates a swap from member updates, as produced by [`MemberSet::swap`].
+        pub fn new(metadata: Sha256Digest, updates: Vec<MemberUpdate>) -> Self {
+            Self {
+                new_metadata: metadata,
+                payload: SwapInner { updates },
+            }
+        }
+
+        pub(super) fn member_ids(&self) -> Result<Vec<[u8; 32]>, Error> {
+            self.payload.updates.iter().map(MemberUpdate::id).collect()
+        }
+
+        /// Applies the swap along with additional updates to untouched
+        /// members, all proven against the current group.
+        pub(super) fn apply_with(
+            self,
+            self_digest: Sha256Digest,
+            persona: &mut Persona,
+            group: &mut Group,
+            advances: Vec<MemberUpdate>,
+        ) -> Result<(), Error> {
+            let mut updates = self.payload.updates;
+            updates.extend(advances);
+
+            *group = group.update(&updates)?;
+            persona.group = *group.root();
+            persona.advance(self.new_metadata, self_digest)?;
+
+            Ok(())
+        }
     }
 
     impl IOperation<Group, Persona> for Swap {
         fn validate(
             &self,
-            self_digest: &Sha256Digest,
+            _self_digest: &Sha256Digest,
             persona: &Persona,
             group: &Group,
         ) -> Result<(), Error> {
-            todo!()
+            // state should be initialized
+            if persona == &Persona::DEFAULT || group.is_empty() {
+                return Err(Error::InvalidOperation("persona not initialized"));
+            }
+
+            if self.payload.updates.is_empty() {
+                return Err(Error::InvalidOperation(
+                    "swap must change at least one member",
+                ));
+            }
+
+            // updates must be proven against the group, and must not empty it
+            if group.update(&self.payload.updates)?.is_empty() {
+                return Err(Error::InvalidOperation(
+                    "group must have at least one member",
+                ));
+            }
+
+            Ok(())
         }
 
         fn apply(
-            mut self,
+            self,
             self_digest: Sha256Digest,
             persona: &mut Persona,
             group: &mut Group,
         ) -> Result<(), Error> {
-            todo!()
+            self.apply_with(self_digest, persona, group, Vec::new())
         }
     }
 }
 
 mod sign {
-
     use super::*;
+    use sha2::Sha512;
 
-    /// ...
+    /// Domain separator of the digest committed to a [`Persona`]'s `msg`.
     pub const DOMAIN_SEPARATOR: &[u8] = b"datalove::persona::sign";
 
-    ///
+    /// The [`Sha512`] prehash of the message to be signed.
     pub type SignPayload = [u8; 64];
 
     /// The `sign` operation, which produces a signature on behalf of a [`Persona`].
     ///
+    /// The resulting proof commits the payload's digest to the persona's
+    /// `msg`, and can be verified as a [`PersonaSignature`] against its DID.
     ///
+    /// [`PersonaSignature`]: crate::PersonaSignature
     pub type Sign = GenericOperation<SignPayload>;
 
+    /// Serde helpers for [`Sign`], whose payload is hex-encoded as it
+    /// exceeds serde's array impls.
+    #[cfg(feature = "serde")]
+    pub(super) mod serde_sign {
+        use super::*;
+        use crate::util::serde_hex;
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        #[derive(Serialize, Deserialize)]
+        struct SignRepr {
+            new_metadata: Sha256Digest,
+            #[serde(with = "serde_hex")]
+            payload: SignPayload,
+        }
+
+        pub fn serialize<S: Serializer>(op: &Sign, serializer: S) -> Result<S::Ok, S::Error> {
+            let (new_metadata, payload) = (op.new_metadata, op.payload);
+            SignRepr {
+                new_metadata,
+                payload,
+            }
+            .serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Sign, D::Error> {
+            let SignRepr {
+                new_metadata,
+                payload,
+            } = SignRepr::deserialize(deserializer)?;
+            Ok(Sign {
+                new_metadata,
+                payload,
+            })
+        }
+    }
+
+    impl Sign {
+        pub fn new(metadata: Sha256Digest, msg: &[u8]) -> Self {
+            Self {
+                new_metadata: metadata,
+                payload: Self::prehash(msg),
+            }
+        }
+
+        /// Computes the payload of a message to be signed.
+        pub fn prehash(msg: &[u8]) -> SignPayload {
+            let mut payload = [0u8; 64];
+            payload.copy_from_slice(&Sha512::digest(msg));
+            payload
+        }
+
+        /// Computes the digest committed to the [`Persona`]'s `msg`.
+        pub fn digest(payload: &SignPayload) -> Sha256Digest {
+            Sha256::new_with_prefix(DOMAIN_SEPARATOR)
+                .chain_update(payload)
+                .into()
+        }
+
+        /// Computes the digest committed to the [`Persona`]'s `msg` when
+        /// signing the given message.
+        pub fn message_digest(msg: &[u8]) -> Sha256Digest {
+            Self::digest(&Self::prehash(msg))
+        }
+    }
+
     impl IOperation<Group, Persona> for Sign {
         fn validate(
             &self,
-            self_digest: &Sha256Digest,
+            _self_digest: &Sha256Digest,
             persona: &Persona,
             group: &Group,
         ) -> Result<(), Error> {
-            todo!()
+            // state should be initialized
+            if persona == &Persona::DEFAULT || group.is_empty() {
+                return Err(Error::InvalidOperation("persona not initialized"));
+            }
+
+            Ok(())
         }
 
         fn apply(
-            mut self,
+            self,
+            _self_digest: Sha256Digest,
+            persona: &mut Persona,
+            _group: &mut Group,
+        ) -> Result<(), Error> {
+            persona.advance(self.new_metadata, Self::digest(&self.payload))?;
+
+            Ok(())
+        }
+    }
+}
+
+mod rotate {
+    use super::*;
+    use sha2::Sha512;
+    use signature::{DigestSigner, Verifier};
+
+    /// The (device) key rotation operation, which replaces a device member's
+    /// key with a new one, retaining its weight.
+    ///
+    /// Authorized solely by the rotated device, and by the new device's
+    /// signature over the [`Persona`]'s head.
+    pub type Rotate = GenericOperation<RotateInner>;
+
+    #[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct RotateInner {
+        /// The device member being rotated.
+        member: Member,
+        /// Proof of the rotated member's presence in the group.
+        member_proof: MerkleProof,
+        /// The new device.
+        device: Device,
+        /// Proof of the new device's absence from the group.
+        device_proof: MerkleProof,
+        /// The new device's signature over the persona's head.
+        signature: DeviceSignature,
+    }
+
+    impl Rotate {
+        pub fn new<Si>(
+            metadata: Sha256Digest,
+            members: &MemberSet,
+            id: &[u8; 32],
+            device: Device,
+            persona: &Persona,
+            signer: &Si,
+        ) -> Result<Self, Error>
+        where
+            Si: DigestSigner<Sha512, DeviceSignature>,
+        {
+            let member = members
+                .get(id)
+                .cloned()
+                .ok_or_else(|| Error::InvalidOperation("member to rotate is not in group"))?;
+            let signature = device.sign_message::<Sha512, _>(persona.head().as_ref(), signer)?;
+            let device_proof = members.proof(&device.id());
+            Ok(Self {
+                new_metadata: metadata,
+                payload: RotateInner {
+                    member,
+                    member_proof: members.proof(id),
+                    device,
+                    device_proof,
+                    signature,
+                },
+            })
+        }
+
+        /// Asserts the rotated device is the sole signer.
+        pub fn verify_signers(&self, sig: &GroupSignature) -> Result<(), Error> {
+            let mut ids = sig.signer_ids();
+            match (ids.next(), ids.next()) {
+                (Some(id), None) if id == self.payload.member.id() => Ok(()),
+                _ => Err(Error::Unauthorized),
+            }
+        }
+
+        pub(super) fn member_ids(&self) -> Result<Vec<[u8; 32]>, Error> {
+            Ok(Vec::from([
+                self.payload.member.id(),
+                self.payload.device.id(),
+            ]))
+        }
+
+        /// Applies the rotation along with additional updates to untouched
+        /// members, all proven against the current group.
+        pub(super) fn apply_with(
+            self,
             self_digest: Sha256Digest,
             persona: &mut Persona,
             group: &mut Group,
+            advances: Vec<MemberUpdate>,
         ) -> Result<(), Error> {
-            todo!()
+            let mut updates = Vec::from(self.updates()?);
+            updates.extend(advances);
+
+            *group = group.update(&updates)?;
+            persona.group = *group.root();
+            persona.advance(self.new_metadata, self_digest)?;
+
+            Ok(())
+        }
+
+        /// Updates that remove the rotated member, and add the new device
+        /// with the same weight.
+        fn updates(&self) -> Result<[MemberUpdate; 2], Error> {
+            let RotateInner {
+                member,
+                member_proof,
+                device,
+                device_proof,
+                ..
+            } = &self.payload;
+
+            let Member::Device(_) = member else {
+                return Err(Error::InvalidOperation(
+                    "only device members can be rotated",
+                ));
+            };
+
+            Ok([
+                MemberUpdate::remove(member.clone(), member_proof.clone()),
+                MemberUpdate::add(
+                    Member::device(member.weight(), device.clone()),
+                    device_proof.clone(),
+                ),
+            ])
+        }
+    }
+
+    impl IOperation<Group, Persona> for Rotate {
+        fn validate(
+            &self,
+            _self_digest: &Sha256Digest,
+            persona: &Persona,
+            group: &Group,
+        ) -> Result<(), Error> {
+            // state should be initialized
+            if persona == &Persona::DEFAULT || group.is_empty() {
+                return Err(Error::InvalidOperation("persona not initialized"));
+            }
+
+            // new device must sign the persona's head
+            self.payload
+                .device
+                .verify(persona.head().as_ref(), &self.payload.signature)?;
+
+            // rotated member must be a device in the group, and the new device must not be
+            group.update(&self.updates()?)?;
+
+            Ok(())
+        }
+
+        fn apply(
+            self,
+            self_digest: Sha256Digest,
+            persona: &mut Persona,
+            group: &mut Group,
+        ) -> Result<(), Error> {
+            self.apply_with(self_digest, persona, group, Vec::new())
         }
     }
 }
@@ -341,9 +774,613 @@ mod sign {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        device::{Device, DeviceLog},
+        util::risc0::TypedJournal,
+        MemberSignature, PersonaSignature,
+    };
+    use ed25519_dalek::SigningKey;
+
+    fn device_member(seed: u8, weight: Weight) -> Member {
+        let pk = SigningKey::from_bytes(&[seed; 32]).verifying_key();
+        Member::device(weight, Device::from(pk))
+    }
+
+    fn member_set(members: Vec<Member>) -> MemberSet {
+        MemberSet::new(members).expect("should be a valid member set")
+    }
+
+    fn init_state(members: &MemberSet) -> (Persona, Group) {
+        let mut persona = Persona::default();
+        let mut group = Group::default();
+        init::Init::new(Sha256Digest::ZERO, members)
+            .apply(Sha256Digest::ZERO, &mut persona, &mut group)
+            .expect("should init");
+        (persona, group)
+    }
+
+    #[test]
+    fn can_init() -> Result<(), Error> {
+        let members = member_set(vec![device_member(1, 1), device_member(2, 2)]);
+        let (mut persona, mut group) = (Persona::default(), Group::default());
+        assert_eq!(persona.seqno, 0);
+
+        let metadata = Sha256Digest::from([7u32; 8]);
+        let op = Operation::Init(init::Init::new(metadata, &members));
+        let op_digest = SignedOperation::op_digest(&op, &persona);
+        let mut sig = GroupSignature::default();
+        for (seed, weight) in [(1, 1), (2, 2)] {
+            let member = device_member(seed, weight);
+            let sk = SigningKey::from_bytes(&[seed; 32]);
+            let member_sig = member.sign(&op_digest, &sk, &mut DeviceLog::default())?;
+            sig = members.add_signature(&member.id(), member_sig, sig)?;
+        }
+        let signed_op = SignedOperation::new(op.clone(), sig.clone());
+
+        let self_digest = Sha256Digest::from([9u32; 8]);
+        signed_op.validate(&self_digest, &persona, &group)?;
+        signed_op
+            .clone()
+            .apply(self_digest, &mut persona, &mut group)?;
+        assert_eq!(group, members.advance(&op, &sig)?.group());
+        assert_eq!(group.len(), 2);
+        assert_eq!(group.weight(), 3);
+        assert_eq!(persona.group, *group.root());
+        assert_eq!(persona.seqno, 1);
+        assert_eq!(persona.metadata, metadata);
+        assert_eq!(persona.op, self_digest);
+        let did: Sha256Digest = Sha256::default()
+            .chain_update(&self_digest)
+            .chain_update(&metadata)
+            .finalize()
+            .into();
+        assert_eq!(persona.did, did);
+
+        // cannot re-initialize a persona
+        assert!(signed_op.validate(&self_digest, &persona, &group).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn can_swap() -> Result<(), Error> {
+        let members = member_set(vec![device_member(1, 1), device_member(2, 1)]);
+        let (mut persona, mut group) = init_state(&members);
+
+        let metadata = Sha256Digest::from([7u32; 8]);
+        let op_digest = Sha256Digest::from([9u32; 8]);
+        let (swapped, updates) = members.swap(&[device_member(1, 1)], &[device_member(3, 2)])?;
+        let op = swap::Swap::new(metadata, updates);
+        op.validate(&op_digest, &persona, &group)?;
+        op.apply(op_digest, &mut persona, &mut group)?;
+
+        assert_eq!(
+            swapped,
+            member_set(vec![device_member(2, 1), device_member(3, 2)])
+        );
+        assert_eq!(group, swapped.group());
+        assert_eq!(group.len(), 2);
+        assert_eq!(group.weight(), 3);
+        assert_eq!(persona.group, *group.root());
+        assert_eq!(persona.seqno, 2);
+        assert_eq!(persona.metadata, metadata);
+        assert_eq!(persona.msg, op_digest);
+        Ok(())
+    }
+
+    #[test]
+    fn can_prove_membership() -> Result<(), Error> {
+        let members = member_set((1..=40).map(|i| device_member(i, 1)).collect());
+        let (persona, group) = init_state(&members);
+        assert_eq!(group.len(), 40);
+
+        let member = device_member(35, 1);
+        let proof = members.proof(&member.id());
+        persona.verify_member(&member, &proof)?;
+        group.verify_member(&member, &proof)?;
+
+        // member must match the committed member
+        assert!(persona
+            .verify_member(&device_member(35, 2), &proof)
+            .is_err());
+
+        // non-members cannot be proven
+        let outsider = device_member(41, 1);
+        assert!(persona
+            .verify_member(&outsider, &members.proof(&outsider.id()))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn can_verify_group_signature() -> Result<(), Error> {
+        let members = member_set((1..=40).map(|i| device_member(i, 1)).collect());
+        let group = members.group();
+        let op_digest = Sha256Digest::from([3u32; 8]);
+
+        let sign = |members: &MemberSet, seed: u8, sig| -> Result<GroupSignature, Error> {
+            let member = device_member(seed, 1);
+            let sk = SigningKey::from_bytes(&[seed; 32]);
+            let member_sig = member.sign(&op_digest, &sk, &mut DeviceLog::default())?;
+            members.add_signature(&member.id(), member_sig, sig)
+        };
+
+        let sig = sign(&members, 35, GroupSignature::default())?;
+        let sig = sign(&members, 1, sig)?;
+        let sig = sign(&members, 40, sig)?;
+        assert_eq!(group.verify_signature(&op_digest, &sig)?, 3);
+        assert!(group.verify_signature(&Sha256Digest::ZERO, &sig).is_err());
+
+        // members cannot sign twice
+        assert!(sign(&members, 1, sig).is_err());
+
+        // non-members cannot sign
+        assert!(sign(&members, 41, GroupSignature::default()).is_err());
+        let outsiders = member_set(vec![device_member(41, 1)]);
+        let sig = sign(&outsiders, 41, GroupSignature::default())?;
+        assert!(group.verify_signature(&op_digest, &sig).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn cannot_exceed_max_members() -> Result<(), Error> {
+        let member = |i: u16| {
+            let mut seed = [0u8; 32];
+            seed[..2].copy_from_slice(&i.to_le_bytes());
+            let pk = SigningKey::from_bytes(&seed).verifying_key();
+            Member::device(1, Device::from(pk))
+        };
+        let max = Group::MAX_MEMBERS as u16;
+        assert!(MemberSet::new((0..=max).map(member)).is_err());
+
+        let members = MemberSet::new((0..max).map(member))?;
+        let (persona, group) = init_state(&members);
+        assert_eq!(group.len(), Group::MAX_MEMBERS);
+
+        // cannot swap in additional members
+        let extra = member(max);
+        assert!(members.swap(&[], &[extra.clone()]).is_err());
+        let proof = members.proof(&extra.id());
+        let op = swap::Swap::new(Sha256Digest::ZERO, vec![MemberUpdate::add(extra, proof)]);
+        assert!(op.validate(&Sha256Digest::ZERO, &persona, &group).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn can_encode_group_signature() -> Result<(), Error> {
+        let members = member_set(vec![device_member(1, 1)]);
+        let member = device_member(1, 1);
+        let sk = SigningKey::from_bytes(&[1; 32]);
+        let member_sig = member.sign(&Sha256Digest::ZERO, &sk, &mut DeviceLog::default())?;
+        let sig = members.add_signature(&member.id(), member_sig, GroupSignature::default())?;
+
+        let mut bytes = borsh::to_vec(&sig)?;
+        assert_eq!(bytes[0], GroupSignature::VERSION);
+        assert_eq!(GroupSignature::try_from_slice(&bytes)?, sig);
+
+        // unsupported versions, including those of Ed25519ph signatures, are rejected
+        for version in [0, 1, 2] {
+            bytes[0] = version;
+            assert!(GroupSignature::try_from_slice(&bytes).is_err());
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn can_serde_signed_operations() -> Result<(), Error> {
+        let members = member_set(vec![device_member(1, 1)]);
+        let member = device_member(1, 1);
+        let sk = SigningKey::from_bytes(&[1; 32]);
+        let signed = |op: Operation| -> Result<SignedOperation, Error> {
+            let op_digest = SignedOperation::op_digest(&op, &Persona::default());
+            let member_sig = member.sign(&op_digest, &sk, &mut DeviceLog::default())?;
+            let sig = members.add_signature(&member.id(), member_sig, GroupSignature::default())?;
+            Ok(SignedOperation::new(op, sig))
+        };
 
-    // fn random_device_member() -> Member {}
+        let ops = [
+            Operation::Init(init::Init::new(Sha256Digest::ZERO, &members)),
+            Operation::Sign(sign::Sign::new(Sha256Digest::ZERO, b"hello world")),
+        ];
+        for op in ops {
+            let op = signed(op)?;
+            let json = serde_json::to_string(&op).expect("should serialize");
+            let decoded: SignedOperation = serde_json::from_str(&json).expect("should deserialize");
+            assert_eq!(borsh::to_vec(&decoded)?, borsh::to_vec(&op)?);
+        }
+
+        // digests and device keys are multibase strings
+        let digest = Sha256Digest::from([7u32; 8]);
+        assert_eq!(serde_json::to_value(digest).unwrap(), digest.to_string());
+        let json = serde_json::to_value(&member).unwrap();
+        let key = json["Device"]["payload"]["key"].as_str().unwrap();
+        assert!(key.starts_with("z6Mk"));
+        assert_eq!(serde_json::from_value::<Member>(json).unwrap(), member);
+
+        // malformed proofs are rejected
+        let mut json = serde_json::to_value(members.proof(&member.id())).unwrap();
+        json["siblings"] = serde_json::json!([digest]);
+        assert!(serde_json::from_value::<MerkleProof>(json).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn requires_threshold_weight() -> Result<(), Error> {
+        let members = member_set((1..=4).map(|i| device_member(i, 1)).collect());
+        let (_, group) = init_state(&members);
+        assert_eq!(group.required_weight(Group::LOW_THRESHOLD), 1);
+        assert_eq!(group.required_weight(Group::MID_THRESHOLD), 2);
+        assert_eq!(group.required_weight(Group::HIGH_THRESHOLD), 3);
+
+        let (_, updates) = members.swap(&[device_member(1, 1)], &[])?;
+        let op = Operation::Swap(swap::Swap::new(Sha256Digest::ZERO, updates));
+        op.verify_weight(3, &group)?;
+        assert!(matches!(
+            op.verify_weight(2, &group),
+            Err(Error::InsufficientWeight {
+                weight: 2,
+                required: 3
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn can_bump() -> Result<(), Error> {
+        let (mut persona, mut group) = init_state(&member_set(vec![device_member(1, 1)]));
+        let prev_group = group.clone();
+
+        let metadata = Sha256Digest::from([5u32; 8]);
+        let op_digest = Sha256Digest::from([6u32; 8]);
+        let op = Bump::new(Some(metadata));
+        op.validate(&op_digest, &persona, &group)?;
+        op.apply(op_digest, &mut persona, &mut group)?;
+        assert_eq!(persona.seqno, 2);
+        assert_eq!(persona.metadata, metadata);
+        assert_eq!(group, prev_group);
+
+        // bumps without metadata retain the current metadata
+        let op = Bump::new(None);
+        op.validate(&op_digest, &persona, &group)?;
+        op.apply(op_digest, &mut persona, &mut group)?;
+        assert_eq!(persona.seqno, 3);
+        assert_eq!(persona.metadata, metadata);
+
+        // cannot bump an uninitialized persona
+        let op = Bump::new(None);
+        assert!(op
+            .validate(&op_digest, &Persona::default(), &Group::default())
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn can_freeze_and_thaw() -> Result<(), Error> {
+        let (mut persona, mut group) = init_state(&member_set(vec![device_member(1, 1)]));
+        let op_digest = Sha256Digest::ZERO;
+
+        // cannot thaw an active persona
+        assert!(Operation::Thaw
+            .validate(&op_digest, &persona, &group)
+            .is_err());
+
+        Operation::Freeze.validate(&op_digest, &persona, &group)?;
+        Operation::Freeze.apply(op_digest, &mut persona, &mut group)?;
+        assert!(persona.is_frozen());
+        assert_eq!(persona.seqno, 2);
+
+        // frozen personas can only be thawed
+        let op = Operation::Sign(Sign::new(persona.metadata, b"hello world"));
+        assert!(matches!(
+            op.validate(&op_digest, &persona, &group),
+            Err(Error::Frozen)
+        ));
+        assert!(matches!(
+            Operation::Freeze.validate(&op_digest, &persona, &group),
+            Err(Error::Frozen)
+        ));
+
+        Operation::Thaw.validate(&op_digest, &persona, &group)?;
+        Operation::Thaw.apply(op_digest, &mut persona, &mut group)?;
+        assert!(persona.is_active());
+        assert_eq!(persona.seqno, 3);
+        op.validate(&op_digest, &persona, &group)?;
+        Ok(())
+    }
+
+    #[test]
+    fn can_rotate() -> Result<(), Error> {
+        let members = member_set(vec![device_member(1, 1), device_member(2, 3)]);
+        let (mut persona, mut group) = init_state(&members);
+        let old_id = device_member(2, 3).id();
+        let new_sk = SigningKey::from_bytes(&[4; 32]);
+        let new_device = Device::from(new_sk.verifying_key());
+
+        // old device must solely sign the rotation
+        let op = Operation::Rotate(Rotate::new(
+            persona.metadata,
+            &members,
+            &old_id,
+            new_device.clone(),
+            &persona,
+            &new_sk,
+        )?);
+        let op_digest = SignedOperation::op_digest(&op, &persona);
+        let sign = |signers: &[(u8, u8)]| -> Result<_, Error> {
+            let mut sig = GroupSignature::default();
+            for (seed, weight) in signers {
+                let member = device_member(*seed, *weight);
+                let sk = SigningKey::from_bytes(&[*seed; 32]);
+                let member_sig = member.sign(&op_digest, &sk, &mut DeviceLog::default())?;
+                sig = members.add_signature(&member.id(), member_sig, sig)?;
+            }
+            Ok(SignedOperation::new(op.clone(), sig))
+        };
+        let signed_op = sign(&[(2, 3)])?;
+        signed_op.validate(&op_digest, &persona, &group)?;
+        assert!(matches!(
+            sign(&[(1, 1)])?.validate(&op_digest, &persona, &group),
+            Err(Error::Unauthorized)
+        ));
+        assert!(matches!(
+            sign(&[(1, 1), (2, 3)])?.validate(&op_digest, &persona, &group),
+            Err(Error::Unauthorized)
+        ));
+
+        signed_op.apply(op_digest, &mut persona, &mut group)?;
+        assert_eq!(
+            group,
+            member_set(vec![device_member(1, 1), Member::device(3, new_device)]).group()
+        );
+        assert_eq!(persona.group, *group.root());
+        assert_eq!(persona.seqno, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn can_advance_device_logs() -> Result<(), Error> {
+        let keys = (1..=2u8)
+            .map(|seed| SigningKey::from_bytes(&[seed; 32]))
+            .collect::<Vec<_>>();
+        let (mut devices, mut logs) = (Vec::new(), Vec::new());
+        for sk in keys.iter() {
+            let (device, log) = Device::init(sk.verifying_key(), sk)?;
+            devices.push(device);
+            logs.push(log);
+        }
+        let members = member_set(
+            devices
+                .iter()
+                .map(|d| Member::device(1, d.clone()))
+                .collect(),
+        );
+        let (mut persona, mut group) = init_state(&members);
+        let id = devices[0].id();
+        let mut fork = logs[0].clone();
+
+        // signing appends the op to the device's log, whose head is recorded by the group
+        let op = Operation::Bump(Bump::new(None));
+        let op_digest = SignedOperation::op_digest(&op, &persona);
+        let member_sig = members
+            .get(&id)
+            .unwrap()
+            .sign(&op_digest, &keys[0], &mut logs[0])?;
+        let sig = members.add_signature(&id, member_sig, GroupSignature::default())?;
+        let signed_op = SignedOperation::new(op.clone(), sig.clone());
+        signed_op.validate(&op_digest, &persona, &group)?;
+        signed_op.apply(op_digest, &mut persona, &mut group)?;
+        assert_eq!(persona.op, op_digest);
+
+        let advanced = members.advance(&op, &sig)?;
+        devices[0].fast_forward(logs[0].head(), &logs[0].consistency_proof(1)?)?;
+        assert_eq!(
+            advanced.get(&id),
+            Some(&Member::device(1, devices[0].clone()))
+        );
+        assert_eq!(
+            advanced.get(&devices[1].id()),
+            members.get(&devices[1].id())
+        );
+        assert_eq!(group, advanced.group());
+        assert_eq!(persona.group, *group.root());
+
+        // forks of the device's log cannot extend its recorded head
+        let op = Operation::Bump(Bump::new(None));
+        let op_digest = SignedOperation::op_digest(&op, &persona);
+        let member_sig = members
+            .get(&id)
+            .unwrap()
+            .sign(&op_digest, &keys[0], &mut fork)?;
+        let sig = advanced.add_signature(&id, member_sig, GroupSignature::default())?;
+        assert!(SignedOperation::new(op.clone(), sig)
+            .validate(&op_digest, &persona, &group)
+            .is_err());
+
+        let member_sig = advanced
+            .get(&id)
+            .unwrap()
+            .sign(&op_digest, &keys[0], &mut logs[0])?;
+        let sig = advanced.add_signature(&id, member_sig, GroupSignature::default())?;
+        SignedOperation::new(op, sig).validate(&op_digest, &persona, &group)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "ml-dsa")]
+    #[test]
+    fn can_swap_to_post_quantum_devices() -> Result<(), Error> {
+        use ml_dsa::{KeyGen, MlDsa44};
+
+        let members = member_set(vec![device_member(1, 1), device_member(2, 1)]);
+        let (mut persona, mut group) = init_state(&members);
+
+        // swap an ed25519 device for an ml-dsa device
+        let keypair = MlDsa44::key_gen_internal(&[3; 32].into());
+        let (pq_device, mut pq_log) =
+            Device::init(keypair.verifying_key().clone(), keypair.signing_key())?;
+        let pq_member = Member::device(1, pq_device);
+        let (swapped, updates) = members.swap(&[device_member(1, 1)], &[pq_member.clone()])?;
+        let op = swap::Swap::new(persona.metadata, updates);
+        op.apply(Sha256Digest::ZERO, &mut persona, &mut group)?;
+        assert_eq!(group, swapped.group());
+
+        // classical and post-quantum devices sign together
+        let op = Operation::Bump(Bump::new(None));
+        let op_digest = SignedOperation::op_digest(&op, &persona);
+        let sk = SigningKey::from_bytes(&[2; 32]);
+        let member_sig = device_member(2, 1).sign(&op_digest, &sk, &mut DeviceLog::default())?;
+        let sig = swapped.add_signature(
+            &device_member(2, 1).id(),
+            member_sig,
+            GroupSignature::default(),
+        )?;
+        let member_sig = pq_member.sign(&op_digest, keypair.signing_key(), &mut pq_log)?;
+        let sig = swapped.add_signature(&pq_member.id(), member_sig, sig)?;
+
+        let signed_op = SignedOperation::new(op.clone(), sig.clone());
+        signed_op.validate(&op_digest, &persona, &group)?;
+        signed_op.apply(op_digest, &mut persona, &mut group)?;
+        assert_eq!(group, swapped.advance(&op, &sig)?.group());
+        Ok(())
+    }
+
+    #[test]
+    fn cannot_rotate_without_new_device_signature() -> Result<(), Error> {
+        let members = member_set(vec![device_member(1, 1), device_member(2, 1)]);
+        let (persona, group) = init_state(&members);
+        let old_id = device_member(1, 1).id();
+        let op_digest = Sha256Digest::ZERO;
+
+        // new device must sign the current head
+        let new_sk = SigningKey::from_bytes(&[3; 32]);
+        let new_device = Device::from(new_sk.verifying_key());
+        let wrong_sk = SigningKey::from_bytes(&[4; 32]);
+        let op = Rotate::new(
+            persona.metadata,
+            &members,
+            &old_id,
+            new_device,
+            &persona,
+            &wrong_sk,
+        )?;
+        assert!(op.validate(&op_digest, &persona, &group).is_err());
+
+        // new device must not already be in the group
+        let dup_sk = SigningKey::from_bytes(&[2; 32]);
+        let dup_device = Device::from(dup_sk.verifying_key());
+        let op = Rotate::new(
+            persona.metadata,
+            &members,
+            &old_id,
+            dup_device,
+            &persona,
+            &dup_sk,
+        )?;
+        assert!(op.validate(&op_digest, &persona, &group).is_err());
+        Ok(())
+    }
 
     #[test]
-    fn can_init() {}
+    fn can_sign() -> Result<(), Error> {
+        let (mut persona, mut group) = init_state(&member_set(vec![device_member(1, 1)]));
+        let did = persona.did;
+
+        let op = Sign::new(persona.metadata, b"hello world");
+        op.validate(&Sha256Digest::ZERO, &persona, &group)?;
+        op.apply(Sha256Digest::ZERO, &mut persona, &mut group)?;
+        assert_eq!(persona.seqno, 2);
+        assert_eq!(persona.msg, Sign::message_digest(b"hello world"));
+
+        let journal = TypedJournal::new(Default::default(), (Sha256Digest::ZERO, persona));
+        let sig = PersonaSignature::from(journal);
+        sig.verify_message(&did, b"hello world")?;
+        assert!(sig.verify_message(&did, b"goodbye world").is_err());
+        assert!(sig
+            .verify_message(&Sha256Digest::ZERO, b"hello world")
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn can_verify_persona_member_signature() {
+        let (child, _) = init_state(&member_set(vec![device_member(1, 1)]));
+        let member = Member::persona(2, child.did);
+        let op_digest = Sha256Digest::from([3u32; 8]);
+
+        let persona_sig = |did, msg| {
+            let persona = Persona {
+                did,
+                msg,
+                ..child.clone()
+            };
+            let journal = TypedJournal::new(Default::default(), (Sha256Digest::ZERO, persona));
+            MemberSignature::persona(2, PersonaSignature::from(journal))
+        };
+
+        // signature must be over the member's weighted op digest
+        let member_op_digest = op_digest ^ 2;
+        let sig = persona_sig(child.did, Sign::message_digest(member_op_digest.as_ref()));
+        assert!(member.verify_signature(&op_digest, &sig).is_ok());
+        let sig = persona_sig(child.did, Sign::message_digest(op_digest.as_ref()));
+        assert!(member.verify_signature(&op_digest, &sig).is_err());
+
+        // the member persona may have advanced since joining the group
+        let advanced = Persona {
+            seqno: child.seqno + 3,
+            metadata: Sha256Digest::from([5u32; 8]),
+            msg: Sign::message_digest(member_op_digest.as_ref()),
+            ..child.clone()
+        };
+        let journal = TypedJournal::new(Default::default(), (Sha256Digest::ZERO, advanced));
+        let sig = MemberSignature::persona(2, PersonaSignature::from(journal));
+        assert!(member.verify_signature(&op_digest, &sig).is_ok());
+
+        // signature must be from the member persona
+        let sig = persona_sig(
+            Sha256Digest::ZERO,
+            Sign::message_digest(member_op_digest.as_ref()),
+        );
+        assert!(member.verify_signature(&op_digest, &sig).is_err());
+    }
+
+    #[test]
+    fn cannot_swap_invalid_members() {
+        let members = member_set(vec![device_member(1, 1), device_member(2, 1)]);
+        let (persona, group) = init_state(&members);
+        let digest = Sha256Digest::ZERO;
+        let swap_op = |update: fn(Member, MerkleProof) -> MemberUpdate, member: Member| {
+            let proof = members.proof(&member.id());
+            swap::Swap::new(digest, vec![update(member, proof)])
+        };
+
+        // removed member must be present
+        assert!(members.swap(&[device_member(3, 1)], &[]).is_err());
+        let op = swap_op(MemberUpdate::remove, device_member(3, 1));
+        assert!(op.validate(&digest, &persona, &group).is_err());
+
+        // added member must not duplicate an existing device
+        assert!(members.swap(&[], &[device_member(2, 3)]).is_err());
+        let op = swap_op(MemberUpdate::add, device_member(2, 3));
+        assert!(op.validate(&digest, &persona, &group).is_err());
+
+        // added member must have a non-zero weight
+        assert!(members.swap(&[], &[device_member(3, 0)]).is_err());
+        let op = swap_op(MemberUpdate::add, device_member(3, 0));
+        assert!(matches!(
+            op.validate(&digest, &persona, &group),
+            Err(Error::InvalidUserWeights)
+        ));
+
+        // group must not be emptied
+        let (_, updates) = members
+            .swap(&[device_member(1, 1), device_member(2, 1)], &[])
+            .expect("should swap");
+        let op = swap::Swap::new(digest, updates);
+        assert!(op.validate(&digest, &persona, &group).is_err());
+
+        // persona must be initialized
+        let op = swap_op(MemberUpdate::add, device_member(3, 1));
+        assert!(op
+            .validate(&digest, &Persona::default(), &Group::default())
+            .is_err());
+    }
 }
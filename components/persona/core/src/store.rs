@@ -0,0 +1,177 @@
+//! Pluggable storage of device logs, persona state machines and receipts.
+
+use crate::{
+    device::{Device, DeviceLog, DeviceLogNode, DeviceSignature},
+    maybestd::vec::Vec,
+    util::Sha256Digest,
+    zksm::{ProverState, StateMachine, VerifierState},
+    Error,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+#[cfg(not(target_os = "zkvm"))]
+use risc0_zkvm::Receipt;
+use sha2::Sha512;
+use signature::DigestSigner;
+
+/// A value that can be persisted to a [`Store`].
+pub trait Storable: Sized {
+    /// Namespace of values of this type within a [`Store`].
+    const NAMESPACE: &'static str;
+
+    fn encode(&self) -> Result<Vec<u8>, Error>;
+
+    fn decode(bytes: &[u8]) -> Result<Self, Error>;
+}
+
+/// A store of [`Storable`] values, keyed by digest within their namespace.
+///
+/// Implementors need only store raw bytes, e.g. in memory or on disk.
+pub trait Store {
+    /// Reads the bytes stored under the key, if any.
+    fn get_bytes(&self, namespace: &str, key: &Sha256Digest) -> Result<Option<Vec<u8>>, Error>;
+
+    /// Writes the bytes under the key, replacing any existing value.
+    fn put_bytes(
+        &mut self,
+        namespace: &str,
+        key: &Sha256Digest,
+        bytes: Vec<u8>,
+    ) -> Result<(), Error>;
+
+    /// Removes any bytes stored under the key.
+    fn remove_bytes(&mut self, namespace: &str, key: &Sha256Digest) -> Result<(), Error>;
+
+    fn get<T: Storable>(&self, key: &Sha256Digest) -> Result<Option<T>, Error> {
+        self.get_bytes(T::NAMESPACE, key)?
+            .map(|bytes| T::decode(&bytes))
+            .transpose()
+    }
+
+    fn contains<T: Storable>(&self, key: &Sha256Digest) -> Result<bool, Error> {
+        Ok(self.get_bytes(T::NAMESPACE, key)?.is_some())
+    }
+
+    fn put<T: Storable>(&mut self, key: &Sha256Digest, value: &T) -> Result<(), Error> {
+        self.put_bytes(T::NAMESPACE, key, value.encode()?)
+    }
+
+    fn remove<T: Storable>(&mut self, key: &Sha256Digest) -> Result<(), Error> {
+        self.remove_bytes(T::NAMESPACE, key)
+    }
+}
+
+/// Device log nodes, keyed by their [`DeviceLogNode::leaf_hash`].
+impl Storable for DeviceLogNode {
+    const NAMESPACE: &'static str = "device_log_node";
+
+    fn encode(&self) -> Result<Vec<u8>, Error> {
+        Ok(borsh::to_vec(self)?)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::try_from_slice(bytes)?)
+    }
+}
+
+/// Complete device logs, keyed by their device's id as a digest, i.e.
+/// `Sha256Digest::from(device.id())`.
+impl Storable for DeviceLog {
+    const NAMESPACE: &'static str = "device_log";
+
+    fn encode(&self) -> Result<Vec<u8>, Error> {
+        Ok(borsh::to_vec(self)?)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::try_from_slice(bytes)?)
+    }
+}
+
+impl DeviceLog {
+    /// Loads a device's log from the store, or an empty log if none has been
+    /// stored yet.
+    pub fn load(store: &impl Store, device: &Device) -> Result<Self, Error> {
+        Ok(store
+            .get(&Sha256Digest::from(device.id()))?
+            .unwrap_or_default())
+    }
+
+    /// Saves a device's log and all of its nodes to the store, e.g. after
+    /// [`Device::init`].
+    pub fn save(&self, store: &mut impl Store, device: &Device) -> Result<(), Error> {
+        for node in self.iter() {
+            store.put(&node.leaf_hash(), node)?;
+        }
+        store.put(&Sha256Digest::from(device.id()), self)
+    }
+}
+
+impl Device {
+    /// Signs and appends an entry to the device's stored log, persisting
+    /// both the new [`DeviceLogNode`] and the updated [`DeviceLog`].
+    ///
+    /// The store is left untouched if the entry cannot be appended.
+    pub fn append_stored<T, Si>(
+        &mut self,
+        entry: &T,
+        signer: &Si,
+        store: &mut impl Store,
+    ) -> Result<DeviceSignature, Error>
+    where
+        T: BorshSerialize,
+        Si: DigestSigner<Sha512, DeviceSignature>,
+    {
+        let mut log = DeviceLog::load(store, self)?;
+        let mut device = self.clone();
+        let signature = device.append(entry, signer, &mut log)?;
+
+        let node = log
+            .get(log.len() - 1)
+            .expect("log should contain the appended node");
+        store.put(&node.leaf_hash(), node)?;
+        store.put(&Sha256Digest::from(device.id()), &log)?;
+
+        *self = device;
+        Ok(signature)
+    }
+}
+
+/// State machine snapshots, keyed by their verifier digest.
+impl<P: ProverState, V: VerifierState> Storable for StateMachine<P, V> {
+    const NAMESPACE: &'static str = "state_machine";
+
+    fn encode(&self) -> Result<Vec<u8>, Error> {
+        Ok(borsh::to_vec(self)?)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::try_from_slice(bytes)?)
+    }
+}
+
+/// Receipts, keyed by the verifier digest of the state machine they prove.
+///
+/// Only stored by hosts, as the guest only handles receipts as assumptions.
+#[cfg(not(target_os = "zkvm"))]
+impl Storable for Receipt {
+    const NAMESPACE: &'static str = "receipt";
+
+    fn encode(&self) -> Result<Vec<u8>, Error> {
+        let words = risc0_zkvm::serde::to_vec(self)
+            .map_err(|_| Error::InvalidEncoding("failed to encode receipt"))?;
+        Ok(words.iter().flat_map(|word| word.to_le_bytes()).collect())
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() % 4 != 0 {
+            return Err(Error::InvalidEncoding("invalid receipt length"));
+        }
+
+        let words = bytes
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect::<Vec<_>>();
+        risc0_zkvm::serde::from_slice(&words)
+            .map_err(|_| Error::InvalidEncoding("failed to decode receipt"))
+    }
+}
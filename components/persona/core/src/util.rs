@@ -1,3 +1,5 @@
+pub mod merkle;
+
 pub use self::digest::DigestPipe;
 pub use risc0::{ImageId, Sha256Digest, Sha256Pipe};
 
@@ -120,10 +122,210 @@ pub mod ed25519 {
     }
 }
 
+pub mod secp256k1 {
+    use super::*;
+    use k256::ecdsa::{Signature, VerifyingKey};
+
+    /// Serializes the key as a compressed SEC1 point.
+    pub fn serialize_key<W: io::Write>(vk: &VerifyingKey, writer: &mut W) -> io::Result<()> {
+        writer.write_all(vk.to_encoded_point(true).as_bytes())
+    }
+
+    pub fn deserialize_key<R: io::Read>(reader: &mut R) -> io::Result<VerifyingKey> {
+        let vk_bytes = <[u8; 33]>::deserialize_reader(reader)?;
+        VerifyingKey::from_sec1_bytes(&vk_bytes).map_err(|_| io::ErrorKind::InvalidData.into())
+    }
+
+    pub fn serialize_signature<W: io::Write>(
+        signature: &Signature,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        writer.write_all(&signature.to_bytes())
+    }
+
+    pub fn deserialize_signature<R: io::Read>(reader: &mut R) -> io::Result<Signature> {
+        let bytes = <[u8; 64]>::deserialize_reader(reader)?;
+        Signature::from_slice(&bytes).map_err(|_| io::ErrorKind::InvalidData.into())
+    }
+}
+
+pub mod p256 {
+    use super::*;
+    use ::p256::ecdsa::{Signature, VerifyingKey};
+
+    /// Serializes the key as a compressed SEC1 point.
+    pub fn serialize_key<W: io::Write>(vk: &VerifyingKey, writer: &mut W) -> io::Result<()> {
+        writer.write_all(vk.to_encoded_point(true).as_bytes())
+    }
+
+    pub fn deserialize_key<R: io::Read>(reader: &mut R) -> io::Result<VerifyingKey> {
+        let vk_bytes = <[u8; 33]>::deserialize_reader(reader)?;
+        VerifyingKey::from_sec1_bytes(&vk_bytes).map_err(|_| io::ErrorKind::InvalidData.into())
+    }
+
+    pub fn serialize_signature<W: io::Write>(
+        signature: &Signature,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        writer.write_all(&signature.to_bytes())
+    }
+
+    pub fn deserialize_signature<R: io::Read>(reader: &mut R) -> io::Result<Signature> {
+        let bytes = <[u8; 64]>::deserialize_reader(reader)?;
+        Signature::from_slice(&bytes).map_err(|_| io::ErrorKind::InvalidData.into())
+    }
+}
+
+#[cfg(feature = "ml-dsa")]
+pub mod ml_dsa {
+    use super::*;
+    use ::ml_dsa::{EncodedSignature, EncodedVerifyingKey, MlDsa44, Signature, VerifyingKey};
+
+    /// Length of an encoded ML-DSA-44 verifying key.
+    pub const KEY_LEN: usize = 1312;
+    /// Length of an encoded ML-DSA-44 signature.
+    pub const SIGNATURE_LEN: usize = 2420;
+
+    pub fn encode_key(vk: &VerifyingKey<MlDsa44>) -> [u8; KEY_LEN] {
+        let mut bytes = [0u8; KEY_LEN];
+        bytes.copy_from_slice(&vk.encode());
+        bytes
+    }
+
+    pub fn decode_key(bytes: &[u8; KEY_LEN]) -> VerifyingKey<MlDsa44> {
+        VerifyingKey::decode(&EncodedVerifyingKey::<MlDsa44>::from(*bytes))
+    }
+
+    pub fn encode_signature(signature: &Signature<MlDsa44>) -> [u8; SIGNATURE_LEN] {
+        let mut bytes = [0u8; SIGNATURE_LEN];
+        bytes.copy_from_slice(&signature.encode());
+        bytes
+    }
+
+    pub fn decode_signature(bytes: &[u8; SIGNATURE_LEN]) -> Option<Signature<MlDsa44>> {
+        Signature::decode(&EncodedSignature::<MlDsa44>::from(*bytes))
+    }
+
+    /// Deserializes an encoded signature, rejecting malformed encodings.
+    pub fn deserialize_signature<R: io::Read>(
+        reader: &mut R,
+    ) -> io::Result<crate::maybestd::boxed::Box<[u8; SIGNATURE_LEN]>> {
+        let bytes = <[u8; SIGNATURE_LEN]>::deserialize_reader(reader)?;
+        decode_signature(&bytes).ok_or(io::ErrorKind::InvalidData)?;
+        Ok(bytes.into())
+    }
+}
+
+/// Serde helpers, encoding signatures and other byte strings as hex.
+///
+/// Used as `#[serde(with = "util::serde_hex")]`.
+#[cfg(feature = "serde")]
+pub mod serde_hex {
+    use super::*;
+    use crate::maybestd::{boxed::Box, string::String};
+    use ::serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+
+    /// A value encoded as a byte string.
+    pub trait HexBytes: Sized {
+        fn to_vec(&self) -> Vec<u8>;
+        fn from_vec(bytes: Vec<u8>) -> Option<Self>;
+    }
+
+    pub fn serialize<T: HexBytes, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(value.to_vec()))
+    }
+
+    pub fn deserialize<'de, T: HexBytes, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let bytes = hex::decode(s).map_err(D::Error::custom)?;
+        T::from_vec(bytes).ok_or_else(|| D::Error::custom("invalid byte string"))
+    }
+
+    impl HexBytes for Vec<u8> {
+        fn to_vec(&self) -> Vec<u8> {
+            self.clone()
+        }
+
+        fn from_vec(bytes: Vec<u8>) -> Option<Self> {
+            Some(bytes)
+        }
+    }
+
+    impl<const N: usize> HexBytes for [u8; N] {
+        fn to_vec(&self) -> Vec<u8> {
+            self.as_slice().into()
+        }
+
+        fn from_vec(bytes: Vec<u8>) -> Option<Self> {
+            bytes.try_into().ok()
+        }
+    }
+
+    impl<const N: usize> HexBytes for Box<[u8; N]> {
+        fn to_vec(&self) -> Vec<u8> {
+            self.as_slice().into()
+        }
+
+        fn from_vec(bytes: Vec<u8>) -> Option<Self> {
+            bytes.try_into().ok()
+        }
+    }
+
+    impl HexBytes for ed25519_dalek::Signature {
+        fn to_vec(&self) -> Vec<u8> {
+            self.to_bytes().into()
+        }
+
+        fn from_vec(bytes: Vec<u8>) -> Option<Self> {
+            Self::from_slice(&bytes).ok()
+        }
+    }
+
+    impl HexBytes for k256::ecdsa::Signature {
+        fn to_vec(&self) -> Vec<u8> {
+            self.to_bytes().to_vec()
+        }
+
+        fn from_vec(bytes: Vec<u8>) -> Option<Self> {
+            Self::from_slice(&bytes).ok()
+        }
+    }
+
+    impl HexBytes for ::p256::ecdsa::Signature {
+        fn to_vec(&self) -> Vec<u8> {
+            self.to_bytes().to_vec()
+        }
+
+        fn from_vec(bytes: Vec<u8>) -> Option<Self> {
+            Self::from_slice(&bytes).ok()
+        }
+    }
+}
+
+/// Private-use multicodecs of persona values, identifying the content of
+/// their CIDs.
+pub mod multicodec {
+    /// A borsh-encoded persona journal, i.e. a state machine's verifier state.
+    pub const PERSONA_JOURNAL: u64 = 0x30_0000;
+    /// A persona's group, addressed by its Merkle root.
+    pub const PERSONA_GROUP: u64 = 0x30_0001;
+    /// A borsh-encoded state machine transition.
+    pub const PERSONA_TRANSITION: u64 = 0x30_0002;
+}
+
 pub mod risc0 {
     use super::*;
+    use ::cid::{multihash::MultihashGeneric, Cid};
     use ::digest::Digest;
     use hex::FromHex;
+    use multibase::Base;
+    #[cfg(not(target_os = "zkvm"))]
+    use risc0_zkvm::Receipt;
     use risc0_zkvm::{
         sha::{self, DIGEST_BYTES, DIGEST_WORDS},
         ReceiptClaim,
@@ -181,6 +383,77 @@ pub mod risc0 {
 
     impl Sha256Digest {
         pub const ZERO: Self = Self(sha::Digest::ZERO);
+
+        /// Multihash code of SHA2-256 digests.
+        pub const MULTIHASH_CODE: u64 = 0x12;
+
+        pub fn to_bytes(&self) -> [u8; DIGEST_BYTES] {
+            bytemuck::cast(*AsRef::<[u32; DIGEST_WORDS]>::as_ref(&self.0))
+        }
+
+        /// The digest as a CIDv1 of content of the given [`multicodec`].
+        pub fn to_cid(&self, codec: u64) -> Cid {
+            let hash = MultihashGeneric::wrap(Self::MULTIHASH_CODE, &self.to_bytes())
+                .expect("should never fail to wrap a 32-byte digest");
+            Cid::new_v1(codec, hash)
+        }
+
+        /// The SHA2-256 digest of a CID, regardless of its codec.
+        pub fn from_cid(cid: &Cid) -> Result<Self, crate::Error> {
+            let hash = cid.hash();
+            if hash.code() != Self::MULTIHASH_CODE {
+                return Err(crate::Error::InvalidEncoding(
+                    "CID must be a SHA2-256 digest",
+                ));
+            }
+
+            let bytes: [u8; DIGEST_BYTES] = hash
+                .digest()
+                .try_into()
+                .map_err(|_| crate::Error::InvalidEncoding("digest must be 32 bytes"))?;
+            Ok(bytes.into())
+        }
+    }
+
+    /// Displays the digest as a base58btc multibase string.
+    impl fmt::Display for Sha256Digest {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(&multibase::encode(Base::Base58Btc, self.to_bytes()))
+        }
+    }
+
+    /// Parses a digest from any multibase string.
+    impl FromStr for Sha256Digest {
+        type Err = crate::Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let (_, bytes) = multibase::decode(s)
+                .map_err(|_| crate::Error::InvalidEncoding("invalid multibase string"))?;
+            let bytes: [u8; DIGEST_BYTES] = bytes
+                .try_into()
+                .map_err(|_| crate::Error::InvalidEncoding("digest must be 32 bytes"))?;
+            Ok(bytes.into())
+        }
+    }
+
+    /// Serializes the digest as its multibase string.
+    #[cfg(feature = "serde")]
+    impl ::serde::Serialize for Sha256Digest {
+        fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<'de> ::serde::Deserialize<'de> for Sha256Digest {
+        fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            use crate::maybestd::string::String;
+            use ::serde::de::Error as _;
+
+            let s = String::deserialize(deserializer)?;
+            s.parse()
+                .map_err(|_| D::Error::custom("invalid multibase digest"))
+        }
     }
 
     impl<T: ?Sized> AsRef<T> for Sha256Digest
@@ -283,6 +556,22 @@ pub mod risc0 {
         ReceiptClaim::decode(&mut words).map_err(|_| io::ErrorKind::InvalidData.into())
     }
 
+    /// Serializes the receipt as its length-prefixed risc0-serialized words.
+    ///
+    /// Only available to hosts, which prove and store receipts.
+    #[cfg(not(target_os = "zkvm"))]
+    pub fn serialize_receipt<W: io::Write>(receipt: &Receipt, writer: &mut W) -> io::Result<()> {
+        risc0_zkvm::serde::to_vec(receipt)
+            .map_err(|_| io::ErrorKind::InvalidData)?
+            .serialize(writer)
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    pub fn deserialize_receipt<R: io::Read>(reader: &mut R) -> io::Result<Receipt> {
+        let words = <Vec<u32>>::deserialize_reader(reader)?;
+        risc0_zkvm::serde::from_slice(&words).map_err(|_| io::ErrorKind::InvalidData.into())
+    }
+
     pub type Sha256Pipe<T> = DigestPipe<Sha256, T>;
 
     /// A digest-ible borsh-encoded type.
@@ -408,6 +697,26 @@ pub mod risc0 {
         }
     }
 
+    /// Serializes the journal as its image id and outputs, whose digest is
+    /// recomputed upon deserialization.
+    #[cfg(feature = "serde")]
+    impl<T: ::serde::Serialize> ::serde::Serialize for TypedJournal<T> {
+        fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            (self.image_id(), self.as_inner()).serialize(serializer)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl<'de, T> ::serde::Deserialize<'de> for TypedJournal<T>
+    where
+        T: BorshSerialize + ::serde::Deserialize<'de>,
+    {
+        fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let (image_id, inner) = <(ImageId, T)>::deserialize(deserializer)?;
+            Ok(Self::new(image_id, inner))
+        }
+    }
+
     // impl<T> Borrow<Sha256Digest> for TypedJournal<T> {
     //     fn borrow(&self) -> &Sha256Digest {
     //         &self.0.borrow()
@@ -440,7 +749,7 @@ pub mod risc0 {
             impl<R: io::Read> io::Read for CopyReader<R> {
                 fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
                     let read = self.0.read(buf)?;
-                    self.1.extend_from_slice(buf);
+                    self.1.extend_from_slice(&buf[..read]);
                     Ok(read)
                 }
             }
@@ -484,3 +793,24 @@ pub mod risc0 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maybestd::string::ToString;
+
+    #[test]
+    fn can_encode_digests() -> Result<(), crate::Error> {
+        let digest = Sha256Digest::from([7u32; 8]);
+        let s = digest.to_string();
+        assert!(s.starts_with('z'));
+        assert_eq!(s.parse::<Sha256Digest>()?, digest);
+        assert!("zabc".parse::<Sha256Digest>().is_err());
+
+        let cid = digest.to_cid(multicodec::PERSONA_JOURNAL);
+        assert_eq!(cid.codec(), multicodec::PERSONA_JOURNAL);
+        assert_eq!(Sha256Digest::from_cid(&cid)?, digest);
+        assert_eq!(cid.to_string().parse::<::cid::Cid>().unwrap(), cid);
+        Ok(())
+    }
+}
@@ -0,0 +1,342 @@
+//! A sparse Merkle tree of 32-byte keys, whose empty subtrees are committed
+//! to as [`Sha256Digest::ZERO`].
+
+use super::{risc0::Sha256, Sha256Digest};
+use crate::{
+    maybestd::{io, vec::Vec},
+    Error,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use digest::Digest;
+
+/// The key of a leaf in the tree.
+pub type Key = [u8; 32];
+
+/// The depth of the tree (i.e. the number of bits in a [`Key`]).
+pub const DEPTH: usize = 256;
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Computes the digest of a non-empty leaf.
+pub fn leaf_hash(key: &Key, value: &Sha256Digest) -> Sha256Digest {
+    Sha256::new_with_prefix(LEAF_PREFIX)
+        .chain_update(key)
+        .chain_update(value)
+        .into()
+}
+
+/// Computes the digest of an inner node, which is empty if both of its
+/// children are empty.
+pub fn node_hash(left: &Sha256Digest, right: &Sha256Digest) -> Sha256Digest {
+    if left == &Sha256Digest::ZERO && right == &Sha256Digest::ZERO {
+        return Sha256Digest::ZERO;
+    }
+
+    Sha256::new_with_prefix(NODE_PREFIX)
+        .chain_update(left)
+        .chain_update(right)
+        .into()
+}
+
+/// Computes the root of a tree from its leaves' keys and values, sorted and
+/// deduplicated by key.
+pub fn root(leaves: &[(Key, Sha256Digest)]) -> Sha256Digest {
+    subtree_root(leaves, 0)
+}
+
+/// Produces a proof of the presence (or absence) of a key within a tree of
+/// leaves, sorted and deduplicated by key.
+pub fn proof(leaves: &[(Key, Sha256Digest)], key: &Key) -> MerkleProof {
+    let mut proof = MerkleProof::default();
+    let mut leaves = leaves;
+    for level in 0..DEPTH {
+        if leaves.is_empty() {
+            break;
+        }
+
+        let (left, right) = split(leaves, level, |(k, _)| k);
+        let (sibling, next) = if bit(key, level) {
+            (subtree_root(left, level + 1), right)
+        } else {
+            (subtree_root(right, level + 1), left)
+        };
+
+        if sibling != Sha256Digest::ZERO {
+            proof.bitmap[level / 8] |= 1 << (7 - level % 8);
+            proof.siblings.push(sibling);
+        }
+        leaves = next;
+    }
+
+    proof
+}
+
+/// Verifies a set of leaf updates against the current root, returning the
+/// root of the updated tree.
+///
+/// Each update's proof must be against the current root, and each key may be
+/// updated at most once.
+pub fn update(root: &Sha256Digest, updates: &mut [Update<'_>]) -> Result<Sha256Digest, Error> {
+    if updates.is_empty() {
+        return Ok(*root);
+    }
+
+    updates.sort_unstable_by(|a, b| a.key.cmp(&b.key));
+    if updates.windows(2).any(|w| w[0].key == w[1].key) {
+        return Err(Error::InvalidMerkleProof("duplicate key in updates"));
+    }
+
+    let (old_root, new_root) = fold(updates, 0);
+    if &old_root != root {
+        return Err(Error::InvalidMerkleProof("proof does not match root"));
+    }
+
+    Ok(new_root)
+}
+
+/// A change to a leaf's value (where `None` is an empty leaf), and the proof
+/// of its current value.
+#[derive(Clone, Debug)]
+pub struct Update<'a> {
+    pub key: Key,
+    pub old: Option<Sha256Digest>,
+    pub new: Option<Sha256Digest>,
+    pub proof: &'a MerkleProof,
+}
+
+impl Update<'_> {
+    fn leaf(&self, value: &Option<Sha256Digest>) -> Sha256Digest {
+        value
+            .as_ref()
+            .map_or(Sha256Digest::ZERO, |v| leaf_hash(&self.key, v))
+    }
+}
+
+/// The non-empty siblings along the path from the root to a leaf.
+#[derive(Clone, Debug, Default, Eq, PartialEq, BorshSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MerkleProof {
+    /// Bitmap of the levels whose sibling is non-empty.
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_hex"))]
+    bitmap: [u8; DEPTH / 8],
+    /// Non-empty siblings, ordered from the root to the leaf.
+    siblings: Vec<Sha256Digest>,
+}
+
+impl MerkleProof {
+    /// Verifies the presence (or absence) of a key's value against the root.
+    pub fn verify(
+        &self,
+        key: &Key,
+        value: Option<&Sha256Digest>,
+        root: &Sha256Digest,
+    ) -> Result<(), Error> {
+        let value = value.copied();
+        let mut updates = [Update {
+            key: *key,
+            old: value,
+            new: value,
+            proof: self,
+        }];
+        update(root, &mut updates).map(|_| ())
+    }
+
+    /// Whether the proof has a sibling for each level set in its bitmap.
+    fn is_well_formed(&self) -> bool {
+        let num_siblings = self
+            .bitmap
+            .iter()
+            .map(|b| b.count_ones() as usize)
+            .sum::<usize>();
+        num_siblings == self.siblings.len()
+    }
+
+    /// Returns the sibling at the given level (i.e. the sibling of the
+    /// node on the path whose depth is `level + 1`).
+    fn sibling(&self, level: usize) -> Sha256Digest {
+        if self.bitmap[level / 8] & (1 << (7 - level % 8)) == 0 {
+            return Sha256Digest::ZERO;
+        }
+
+        let idx = self.bitmap[..level / 8]
+            .iter()
+            .map(|b| b.count_ones() as usize)
+            .sum::<usize>()
+            + ((self.bitmap[level / 8] as u16) >> (8 - level % 8)).count_ones() as usize;
+        self.siblings
+            .get(idx)
+            .copied()
+            .unwrap_or(Sha256Digest::ZERO)
+    }
+}
+
+impl BorshDeserialize for MerkleProof {
+    fn deserialize_reader<R: io::Read>(reader: &mut R) -> Result<Self, io::Error> {
+        let bitmap = <[u8; DEPTH / 8]>::deserialize_reader(reader)?;
+        let siblings = Vec::deserialize_reader(reader)?;
+        let proof = Self { bitmap, siblings };
+        if !proof.is_well_formed() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "mismatch between proof bitmap and siblings",
+            ));
+        }
+
+        Ok(proof)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for MerkleProof {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error as _;
+
+        #[derive(serde::Deserialize)]
+        struct Proof {
+            #[serde(with = "super::serde_hex")]
+            bitmap: [u8; DEPTH / 8],
+            siblings: Vec<Sha256Digest>,
+        }
+
+        let Proof { bitmap, siblings } = Proof::deserialize(deserializer)?;
+        let proof = Self { bitmap, siblings };
+        if !proof.is_well_formed() {
+            return Err(D::Error::custom(
+                "mismatch between proof bitmap and siblings",
+            ));
+        }
+
+        Ok(proof)
+    }
+}
+
+/// Folds the updates (sorted by key, and sharing the first `level` bits of
+/// their keys) into the old and new digests of their subtree.
+fn fold(updates: &[Update<'_>], level: usize) -> (Sha256Digest, Sha256Digest) {
+    if level == DEPTH {
+        let update = &updates[0];
+        return (update.leaf(&update.old), update.leaf(&update.new));
+    }
+
+    let (left, right) = split(updates, level, |u| &u.key);
+    let (l, r) = match (left.first(), right.first()) {
+        (Some(_), Some(_)) => (fold(left, level + 1), fold(right, level + 1)),
+        (Some(u), None) => {
+            let sibling = u.proof.sibling(level);
+            (fold(left, level + 1), (sibling, sibling))
+        }
+        (None, Some(u)) => {
+            let sibling = u.proof.sibling(level);
+            ((sibling, sibling), fold(right, level + 1))
+        }
+        (None, None) => unreachable!("updates must be non-empty"),
+    };
+
+    let old = node_hash(&l.0, &r.0);
+    let new = if l.0 == l.1 && r.0 == r.1 {
+        old
+    } else {
+        node_hash(&l.1, &r.1)
+    };
+    (old, new)
+}
+
+/// Computes the digest of a subtree of leaves sharing the first `level` bits
+/// of their keys.
+fn subtree_root(leaves: &[(Key, Sha256Digest)], level: usize) -> Sha256Digest {
+    match leaves {
+        [] => Sha256Digest::ZERO,
+        [(key, value)] if level == DEPTH => leaf_hash(key, value),
+        _ => {
+            let (left, right) = split(leaves, level, |(k, _)| k);
+            node_hash(
+                &subtree_root(left, level + 1),
+                &subtree_root(right, level + 1),
+            )
+        }
+    }
+}
+
+/// Splits items sorted by key (and sharing the first `level` bits of their
+/// keys) into those whose next bit is unset and set.
+fn split<T>(items: &[T], level: usize, key: impl Fn(&T) -> &Key) -> (&[T], &[T]) {
+    let mid = items.partition_point(|item| !bit(key(item), level));
+    items.split_at(mid)
+}
+
+#[inline]
+fn bit(key: &Key, level: usize) -> bool {
+    (key[level / 8] >> (7 - level % 8)) & 1 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(keys: &[u8]) -> Vec<(Key, Sha256Digest)> {
+        keys.iter()
+            .map(|k| ([*k; 32], Sha256Digest::from([*k as u32; 8])))
+            .collect()
+    }
+
+    #[test]
+    fn can_prove_membership() -> Result<(), Error> {
+        let leaves = leaves(&[1, 2, 3, 200]);
+        let root = root(&leaves);
+        assert_eq!(super::root(&[]), Sha256Digest::ZERO);
+
+        for (key, value) in leaves.iter() {
+            proof(&leaves, key).verify(key, Some(value), &root)?;
+            assert!(proof(&leaves, key).verify(key, None, &root).is_err());
+        }
+
+        // absent keys
+        let absent = [4u8; 32];
+        proof(&leaves, &absent).verify(&absent, None, &root)?;
+        assert!(proof(&leaves, &absent)
+            .verify(&absent, Some(&Sha256Digest::ZERO), &root)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn can_update() -> Result<(), Error> {
+        let old_leaves = leaves(&[1, 2, 3, 200]);
+        let new_leaves = leaves(&[1, 3, 4, 200]);
+        let old_root = root(&old_leaves);
+
+        // remove 2, add 4, and read 3
+        let (key_2, key_3, key_4) = ([2u8; 32], [3u8; 32], [4u8; 32]);
+        let (proof_2, proof_3, proof_4) = (
+            proof(&old_leaves, &key_2),
+            proof(&old_leaves, &key_3),
+            proof(&old_leaves, &key_4),
+        );
+        let mut updates = [
+            Update {
+                key: key_4,
+                old: None,
+                new: Some(new_leaves[2].1),
+                proof: &proof_4,
+            },
+            Update {
+                key: key_2,
+                old: Some(old_leaves[1].1),
+                new: None,
+                proof: &proof_2,
+            },
+            Update {
+                key: key_3,
+                old: Some(old_leaves[2].1),
+                new: Some(old_leaves[2].1),
+                proof: &proof_3,
+            },
+        ];
+        assert_eq!(update(&old_root, &mut updates)?, root(&new_leaves));
+
+        // updates must match the current root
+        assert!(update(&root(&new_leaves), &mut updates).is_err());
+        Ok(())
+    }
+}
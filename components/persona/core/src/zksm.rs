@@ -18,6 +18,10 @@ pub trait ProverState: Debug + Default + BorshSerialize + BorshDeserialize {
 pub trait VerifierState: Debug + Default + BorshSerialize + BorshDeserialize {
     // /// Verifier's commitment to the prover state.
     // fn prover_commitment(&self) -> &Sha256Digest;
+
+    /// Records the verifier digest of the state machine a transition is being
+    /// applied to, so that a journal can commit to the journal it extends.
+    fn set_prev(&mut self, _prev: &Sha256Digest) {}
 }
 
 ///
@@ -59,6 +63,21 @@ pub struct VState<V> {
     state: V,
 }
 
+impl<V> VState<V> {
+    /// Commitment to the prover state.
+    pub fn commitment(&self) -> &Sha256Digest {
+        &self.commitment
+    }
+
+    pub fn state(&self) -> &V {
+        &self.state
+    }
+
+    pub fn into_state(self) -> V {
+        self.state
+    }
+}
+
 impl<P: ProverState, V: VerifierState> StateMachine<P, V> {
     /// Produces the default state machine (with appropriate commitments) given the image_id of the guest that is executing it.
     pub fn new(self_image_id: Sha256Digest) -> Self {
@@ -237,7 +256,9 @@ impl<P: ProverState, V: VerifierState> StateMachine<P, V> {
     where
         Op: Operation<P, V>,
     {
+        let prev = transition.op_commitment().clone();
         let (prover, verifier) = self.as_mut_state();
+        verifier.set_prev(&prev);
         let (op_digest, op) = transition.into_op_parts();
         op.apply(op_digest, verifier, prover)?;
 
@@ -276,7 +297,8 @@ pub struct TransitionOp<Op> {
 }
 
 impl<Op> Transition<Op> {
-    fn as_op(&self) -> (Ref<Sha256Digest>, &Op) {
+    /// The transition's digest, and its operation.
+    pub fn as_op(&self) -> (Ref<Sha256Digest>, &Op) {
         (self.digest.borrow(), &self.t.op)
     }
 
@@ -284,7 +306,8 @@ impl<Op> Transition<Op> {
         (self.digest.into_inner(), self.t.op)
     }
 
-    fn op_commitment(&self) -> &Sha256Digest {
+    /// The verifier digest of the state machine the transition applies to.
+    pub fn op_commitment(&self) -> &Sha256Digest {
         &self.t.commitment
     }
 }
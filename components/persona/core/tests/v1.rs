@@ -1,27 +1,205 @@
-use borsh::{BorshDeserialize, BorshSerialize};
+use anyhow::Result;
+use borsh::to_vec;
 use datalove_persona_core::{
-    util::Sha256Digest,
-    zksm::{Operation, ProverState, StateMachine, VerifierState},
-    Error, Group, Member, MemberSignature, SignedOperation,
+    util::{self, Sha256Digest},
+    zksm::StateMachine,
+    Device, DeviceLog, Group, GroupSignature, Init, Member, MemberSet, Operation, Persona,
+    SignedOperation,
 };
-use datalove_persona_risc0::DATALOVE_PERSONA_RISC0_GUEST_V1_ELF as V1_ELF;
-use risc0_zkvm::{default_prover, ExecutorEnv, ProverOpts, VerifierContext};
+use datalove_persona_risc0::{
+    DATALOVE_PERSONA_RISC0_GUEST_V1_ELF as V1_ELF, DATALOVE_PERSONA_RISC0_GUEST_V1_ID as V1_ID,
+};
+use ed25519_dalek::SigningKey;
+use rand::rngs::OsRng;
+use risc0_zkvm::{default_executor, default_prover, ExecutorEnv, Receipt};
 use std::io;
 
-fn random_member() -> Member {
-    todo!()
+type PersonaSM = StateMachine<Group, Persona>;
+
+fn random_member(weight: u8) -> (SigningKey, Member) {
+    let sk = SigningKey::generate(&mut OsRng);
+    let member = Member::device(weight, Device::from(sk.verifying_key()));
+    (sk, member)
+}
+
+/// Signs the operation with each of the provided group members, whose devices
+/// have empty logs.
+fn sign_op(
+    op: Operation,
+    persona: &Persona,
+    members: &MemberSet,
+    signers: &[(&SigningKey, &Member)],
+) -> Result<SignedOperation> {
+    let op_digest = SignedOperation::op_digest(&op, persona);
+    let mut signature = GroupSignature::default();
+    for (sk, member) in signers {
+        let member_sig = member.sign(&op_digest, *sk, &mut DeviceLog::default())?;
+        signature = members.add_signature(&member.id(), member_sig, signature)?;
+    }
+    Ok(SignedOperation::new(op, signature))
+}
+
+/// Creates an [`Init`] operation signed by all of the members.
+fn signed_init(
+    members: &[(SigningKey, Member)],
+    metadata: Sha256Digest,
+) -> Result<(MemberSet, SignedOperation)> {
+    let member_set = MemberSet::new(members.iter().map(|(_, m)| m.clone()))?;
+    let op = Operation::Init(Init::new(metadata, &member_set));
+    let signers = members
+        .iter()
+        .map(|(sk, member)| (sk, member))
+        .collect::<Vec<_>>();
+    let op = sign_op(op, &Persona::DEFAULT, &member_set, &signers)?;
+    Ok((member_set, op))
+}
+
+fn prove_transition(
+    op: SignedOperation,
+    prev: Option<(PersonaSM, Receipt)>,
+) -> Result<(PersonaSM, Receipt)> {
+    // init proving env
+    let mut stdout = Vec::new();
+    let mut env_builder = ExecutorEnv::builder();
+
+    // serialize transition and prev receipt as an assumption, and sm if provided
+    let (transition, sm) = match prev {
+        None => {
+            let sm = PersonaSM::new(V1_ID.into());
+            let transition = sm.new_transition(op);
+            (transition, None)
+        }
+        Some((sm, receipt)) => {
+            let transition = sm.new_transition(op);
+            env_builder.add_assumption(receipt.into());
+            (transition, Some(sm))
+        }
+    };
+
+    // prove
+    let receipt = {
+        let env = env_builder
+            .stdout(&mut stdout)
+            .env_var("SELF_IMAGE_ID", &hex::encode(bytemuck::cast_slice(&V1_ID)))
+            .write_slice(&to_vec(&transition)?)
+            .write_slice(&to_vec(&sm)?)
+            .build()?;
+
+        let receipt = default_prover().prove(env, V1_ELF)?;
+        drop(env_builder);
+        receipt
+    };
+
+    let sm = PersonaSM::load(io::Read::chain(
+        stdout.as_slice(),
+        receipt.journal.bytes.as_slice(),
+    ))?;
+
+    Ok((sm, receipt))
+}
+
+#[test]
+fn can_prove_init() -> Result<()> {
+    let members = (0..3).map(|_| random_member(1)).collect::<Vec<_>>();
+    let metadata = Sha256Digest::from([1u32; 8]);
+    let (member_set, op) = signed_init(&members, metadata)?;
+    let member_set = member_set.advance(op.as_op(), op.signature())?;
+
+    let (sm, receipt) = prove_transition(op, None)?;
+    let (prover, verifier) = sm.as_ref();
+    assert_eq!(prover, &member_set.group());
+    assert_eq!(verifier.group(), member_set.group().root());
+    assert_eq!(verifier.seqno(), 1);
+    assert_eq!(verifier.metadata(), &metadata);
+    assert_ne!(verifier.did(), &Sha256Digest::ZERO);
+    assert_eq!(sm.verifier_commitment(), &sm.prover_digest());
+
+    // receipts roundtrip through their borsh encoding
+    let mut bytes = Vec::new();
+    util::risc0::serialize_receipt(&receipt, &mut bytes)?;
+    let decoded = util::risc0::deserialize_receipt(&mut bytes.as_slice())?;
+    assert_eq!(decoded.journal.bytes, receipt.journal.bytes);
+
+    Ok(())
+}
+
+/// Executes an [`Init`] signed by a group of the given number of members,
+/// returning the guest's cycle count for verifying their signatures (as
+/// traced by [`util::risc0::trace`]).
+fn signature_cycles(num_signers: usize) -> Result<usize> {
+    let members = (0..num_signers)
+        .map(|_| random_member(1))
+        .collect::<Vec<_>>();
+    let (_, op) = signed_init(&members, Sha256Digest::ZERO)?;
+    init_cycles(op, num_signers)
+}
+
+/// Executes a signed [`Init`], returning the guest's cycle count for
+/// verifying its signatures.
+fn init_cycles(op: SignedOperation, num_signers: usize) -> Result<usize> {
+    let transition = PersonaSM::new(V1_ID.into()).new_transition(op);
+
+    let mut stderr = Vec::new();
+    let env = ExecutorEnv::builder()
+        .stderr(&mut stderr)
+        .env_var("SELF_IMAGE_ID", &hex::encode(bytemuck::cast_slice(&V1_ID)))
+        .write_slice(&to_vec(&transition)?)
+        .write_slice(&to_vec(&None::<PersonaSM>)?)
+        .build()?;
+    default_executor().execute(env, V1_ELF)?;
+
+    // i.e. `<guest> [+{cycles}] verified {num_signers} signer signatures`
+    let suffix = format!("] verified {} signer signatures", num_signers);
+    String::from_utf8(stderr)?
+        .lines()
+        .find_map(|line| line.strip_suffix(&suffix)?.split_once("[+")?.1.parse().ok())
+        .ok_or_else(|| anyhow::anyhow!("missing signature verification trace"))
+}
+
+/// Records the guest's cycle counts for verifying group signatures of 5, 10
+/// and 30 signers, along with the cost per signer.
+///
+/// Ed25519 signatures are verified with the batch equation even when alone,
+/// so there is no non-batched path in the guest to compare against.
+#[test]
+#[ignore = "benchmark"]
+fn bench_group_signature() -> Result<()> {
+    for num_signers in [1, 5, 10, 30] {
+        let cycles = signature_cycles(num_signers)?;
+        util::risc0::trace(
+            format_args!(
+                "{} signers: {} cycles, {} cycles per signer",
+                num_signers,
+                cycles,
+                cycles / num_signers
+            ),
+            None,
+        );
+    }
+
+    Ok(())
 }
 
+/// Records the guest's cycle count for verifying a single ML-DSA-44
+/// signature, for comparison with [`bench_group_signature`].
+///
+/// Requires the guest to be built with its `ml-dsa` feature.
+#[cfg(feature = "ml-dsa")]
 #[test]
-fn can_prove() -> io::Result<()> {
-    // let op = Operation::Init()
+#[ignore = "benchmark"]
+fn bench_ml_dsa_signature() -> Result<()> {
+    use ml_dsa::{KeyGen, MlDsa44};
 
-    // let env = ExecutorEnv::builder()
-    //     .write_slice(&[20])
-    //     .build()
-    //     .expect("cannot build env");
-    // let prover = default_prover();
-    // let receipt = prover.prove(env, V1_ELF).expect("cannot prove");
+    let keypair = MlDsa44::key_gen_internal(&[1; 32].into());
+    let (device, mut log) = Device::init(keypair.verifying_key().clone(), keypair.signing_key())?;
+    let member = Member::device(1, device);
+    let members = MemberSet::new([member.clone()])?;
+    let op = Operation::Init(Init::new(Sha256Digest::ZERO, &members));
+    let op_digest = SignedOperation::op_digest(&op, &Persona::DEFAULT);
+    let member_sig = member.sign(&op_digest, keypair.signing_key(), &mut log)?;
+    let sig = members.add_signature(&member.id(), member_sig, GroupSignature::default())?;
 
+    let cycles = init_cycles(SignedOperation::new(op, sig), 1)?;
+    util::risc0::trace(format_args!("1 ml-dsa signer: {} cycles", cycles), None);
     Ok(())
 }
@@ -0,0 +1,231 @@
+//! W3C DID documents of `did:datalove` personas.
+
+use crate::{PersonaVerifier, VerifiedPersona};
+use datalove_persona_core::{
+    DidMetadata, Error, Member, MerkleProof, Persona as PersonaState, PersonaDid,
+};
+use multibase::Base;
+use risc0_zkvm::Receipt;
+use serde::Serialize;
+
+/// A W3C DID document of a persona, serializable as JSON(-LD).
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidDocument {
+    #[serde(rename = "@context")]
+    context: Vec<&'static str>,
+    id: String,
+    /// DIDs of the persona members of the persona's group.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    controller: Vec<String>,
+    /// Keys of the device members of the persona's group.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    verification_method: Vec<VerificationMethod>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    authentication: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    service: Vec<Service>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationMethod {
+    id: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    controller: String,
+    public_key_multibase: String,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Service {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    service_endpoint: String,
+}
+
+impl DidDocument {
+    const CONTEXT: [&'static str; 2] = [
+        "https://www.w3.org/ns/did/v1",
+        "https://w3id.org/security/multikey/v1",
+    ];
+
+    /// Produces the DID document of a persona, from the group members and
+    /// metadata it chooses to disclose.
+    ///
+    /// Each member must be proven to belong to the persona's group, and the
+    /// metadata must match the persona's committed metadata. Undisclosed
+    /// members and metadata are omitted from the document.
+    pub fn new(
+        persona: &PersonaState,
+        members: &[(Member, MerkleProof)],
+        metadata: Option<&DidMetadata>,
+    ) -> Result<Self, Error> {
+        let did = PersonaDid::new(*persona.did()).to_string();
+        let mut doc = Self {
+            context: Self::CONTEXT.to_vec(),
+            id: did.clone(),
+            controller: Vec::new(),
+            verification_method: Vec::new(),
+            authentication: Vec::new(),
+            service: Vec::new(),
+        };
+
+        for (member, proof) in members {
+            persona.verify_member(member, proof)?;
+            match member {
+                Member::Device(device) => {
+                    let device = device.payload();
+                    let id = format!("{}#{}", did, hex::encode(device.id()));
+                    let key = device.public_key_multicodec();
+                    doc.authentication.push(id.clone());
+                    doc.verification_method.push(VerificationMethod {
+                        id,
+                        kind: "Multikey",
+                        controller: did.clone(),
+                        public_key_multibase: multibase::encode(Base::Base58Btc, key),
+                    });
+                }
+                Member::Persona(member) => {
+                    let controller = PersonaDid::new(*member.payload());
+                    doc.controller.push(controller.to_string());
+                }
+                _ => return Err(Error::InvalidDid("unsupported member type")),
+            }
+        }
+
+        if let Some(metadata) = metadata {
+            if &metadata.digest() != persona.metadata() {
+                return Err(Error::InvalidDid("metadata does not match persona"));
+            }
+
+            doc.service = metadata
+                .services()
+                .iter()
+                .map(|service| Service {
+                    id: format!("{}#{}", did, service.id()),
+                    kind: service.kind().into(),
+                    service_endpoint: service.endpoint().into(),
+                })
+                .collect();
+        }
+
+        Ok(doc)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn controller(&self) -> &[String] {
+        &self.controller
+    }
+
+    pub fn verification_method(&self) -> &[VerificationMethod] {
+        &self.verification_method
+    }
+
+    pub fn service(&self) -> &[Service] {
+        &self.service
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self)
+            .map_err(|_| Error::InvalidDid("failed to serialize DID document"))
+    }
+}
+
+impl VerificationMethod {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn public_key_multibase(&self) -> &str {
+        &self.public_key_multibase
+    }
+}
+
+impl Service {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn service_endpoint(&self) -> &str {
+        &self.service_endpoint
+    }
+}
+
+impl VerifiedPersona {
+    /// Produces the DID document of the verified persona.
+    ///
+    /// See [`DidDocument::new`].
+    pub fn did_document(
+        &self,
+        members: &[(Member, MerkleProof)],
+        metadata: Option<&DidMetadata>,
+    ) -> Result<DidDocument, Error> {
+        DidDocument::new(self.state(), members, metadata)
+    }
+}
+
+impl PersonaVerifier {
+    /// Verifies the persona's latest receipt, and resolves its DID document.
+    pub fn resolve(
+        &self,
+        receipt: &Receipt,
+        members: &[(Member, MerkleProof)],
+        metadata: Option<&DidMetadata>,
+    ) -> Result<DidDocument, Error> {
+        self.verify(receipt)?.did_document(members, metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datalove_persona_core::{
+        util::Sha256Digest, zksm::Operation, Device, DidService, Group, Init, MemberSet,
+    };
+    use ed25519_dalek::SigningKey;
+
+    #[test]
+    fn can_resolve_did_document() -> Result<(), Error> {
+        let device = |seed| Device::from(SigningKey::from_bytes(&[seed; 32]).verifying_key());
+        let members = MemberSet::new([Member::device(1, device(1)), Member::device(1, device(2))])?;
+        let metadata = DidMetadata::new(vec![DidService::new(
+            "home".into(),
+            "LinkedDomains".into(),
+            "https://datalove.app".into(),
+        )]);
+
+        let (mut persona, mut group) = (PersonaState::default(), Group::default());
+        Init::new(metadata.digest(), &members).apply(
+            Sha256Digest::ZERO,
+            &mut persona,
+            &mut group,
+        )?;
+        let did = PersonaDid::new(*persona.did()).to_string();
+
+        // only disclosed members and metadata are included
+        let member = Member::device(1, device(1));
+        let disclosed = [(member.clone(), members.proof(&member.id()))];
+        let doc = DidDocument::new(&persona, &disclosed, Some(&metadata))?;
+        assert_eq!(doc.id(), did);
+        assert_eq!(doc.verification_method().len(), 1);
+        assert!(doc.verification_method()[0]
+            .public_key_multibase()
+            .starts_with("z6Mk"));
+        assert_eq!(doc.service()[0].id(), format!("{}#home", did));
+        assert!(doc.to_json()?.contains("\"serviceEndpoint\""));
+        assert!(DidDocument::new(&persona, &[], None)?.service().is_empty());
+
+        // disclosures must match the persona
+        let outsider = Member::device(1, device(3));
+        let proof = members.proof(&outsider.id());
+        assert!(DidDocument::new(&persona, &[(outsider, proof)], None).is_err());
+        assert!(DidDocument::new(&persona, &[], Some(&DidMetadata::default())).is_err());
+        Ok(())
+    }
+}
@@ -53,3 +53,23 @@
 //! the user's previous [`risc0_zkvm::Receipt`]), thus creating the user's
 //! commit log.
 //!
+
+#[cfg(feature = "did")]
+mod did;
+mod persona;
+#[cfg(feature = "prove")]
+mod prover;
+#[cfg(feature = "std")]
+mod store;
+#[cfg(feature = "verify")]
+mod verifier;
+
+#[cfg(feature = "did")]
+pub use did::{DidDocument, Service, VerificationMethod};
+pub use persona::{Persona, PersonaStateMachine};
+#[cfg(feature = "prove")]
+pub use prover::PersonaProver;
+#[cfg(feature = "std")]
+pub use store::{FileStore, MemoryStore};
+#[cfg(feature = "verify")]
+pub use verifier::{PersonaVerifier, VerifiedPersona};
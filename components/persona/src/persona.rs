@@ -0,0 +1,105 @@
+//! A host-side [`Persona`], bundling its state machine with its latest proof.
+
+use datalove_persona_core::{
+    borsh::{io, BorshDeserialize, BorshSerialize},
+    util::{risc0, risc0::TypedJournal, ImageId},
+    zksm::{StateMachine, VState},
+    Did, Error, Group, Persona as PersonaState,
+};
+use risc0_zkvm::Receipt;
+
+/// The state machine of a persona, whose prover state is its [`Group`].
+pub type PersonaStateMachine = StateMachine<Group, PersonaState>;
+
+/// A persona's current state, and the [`Receipt`] proving its latest
+/// transition.
+///
+/// Encodes to a single blob, so that a persona can be stored or shipped as
+/// one value.
+///
+/// A persona's receipt is verified against the expected guest image id when
+/// it is created with [`Persona::new`], but not when it is decoded, which is
+/// intended for reading personas back from trusted storage.
+#[derive(Clone, Debug)]
+pub struct Persona {
+    state: PersonaStateMachine,
+    receipt: Receipt,
+}
+
+impl Persona {
+    /// Bundles a state machine with the receipt whose journal commits to its
+    /// verifier state, verifying that the receipt was proven by the guest
+    /// with the given image id.
+    pub fn new(
+        state: PersonaStateMachine,
+        receipt: Receipt,
+        image_id: impl Into<ImageId>,
+    ) -> Result<Self, Error> {
+        let persona = Self::new_unchecked(state, receipt);
+        persona.verify(image_id)?;
+        Ok(persona)
+    }
+
+    /// Bundles a state machine with its receipt without verifying either,
+    /// e.g. when reading a persona back from trusted storage.
+    pub fn new_unchecked(state: PersonaStateMachine, receipt: Receipt) -> Self {
+        Self { state, receipt }
+    }
+
+    /// Verifies that the receipt's journal commits to the persona's state,
+    /// and that the receipt was proven by the guest with the given image id.
+    pub fn verify(&self, image_id: impl Into<ImageId>) -> Result<(), Error> {
+        let image_id = image_id.into();
+        let journal =
+            TypedJournal::<VState<PersonaState>>::try_from_slice(&self.receipt.journal.bytes)?;
+        if journal.image_id() != &image_id {
+            return Err(Error::ImageIdMismatch {
+                expected: image_id,
+                found: *journal.image_id(),
+            });
+        }
+        if *journal.digest() != self.state.verifier_digest() {
+            return Err(Error::Risc0VerificationError(
+                "receipt journal does not match persona state",
+            ));
+        }
+
+        self.receipt
+            .verify(image_id.to_bytes())
+            .map_err(|_| Error::Risc0VerificationError("receipt failed verification"))
+    }
+
+    pub fn did(&self) -> &Did {
+        self.state.verifier_state_ref().did()
+    }
+
+    pub fn state(&self) -> &PersonaStateMachine {
+        &self.state
+    }
+
+    pub fn receipt(&self) -> &Receipt {
+        &self.receipt
+    }
+
+    pub fn into_parts(self) -> (PersonaStateMachine, Receipt) {
+        (self.state, self.receipt)
+    }
+}
+
+impl BorshSerialize for Persona {
+    fn serialize<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.state.serialize(writer)?;
+        risc0::serialize_receipt(&self.receipt, writer)
+    }
+}
+
+/// Deserializes a persona without verifying it, as in
+/// [`Persona::new_unchecked`]; call [`Persona::verify`] on personas from
+/// untrusted sources.
+impl BorshDeserialize for Persona {
+    fn deserialize_reader<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let state = PersonaStateMachine::deserialize_reader(reader)?;
+        let receipt = risc0::deserialize_receipt(reader)?;
+        Ok(Self::new_unchecked(state, receipt))
+    }
+}
@@ -0,0 +1,333 @@
+//! Proves [`SignedOperation`]s against a [`Persona`] within the zkVM.
+
+use crate::{Persona, PersonaStateMachine};
+use datalove_persona_core::{borsh, util::ImageId, zksm::Transition, Error, SignedOperation};
+use datalove_persona_risc0::{
+    DATALOVE_PERSONA_RISC0_GUEST_V1_ELF as V1_ELF, DATALOVE_PERSONA_RISC0_GUEST_V1_ID as V1_ID,
+};
+use risc0_zkvm::{
+    default_prover, get_prover_server, stark_to_snark, ExecutorEnv, Groth16Receipt, InnerReceipt,
+    ProverOpts, Receipt,
+};
+use std::io;
+
+/// Proves persona transitions with a persona guest, by default the v1 guest.
+///
+/// Receipts are compressed into succinct receipts by default, resolving the
+/// assumption of the previous receipt, so that their size is constant over a
+/// persona's history.
+#[derive(Clone, Debug)]
+pub struct PersonaProver {
+    elf: &'static [u8],
+    image_id: ImageId,
+    compress: bool,
+}
+
+impl PersonaProver {
+    /// A prover for the given guest, whose image id must match its ELF.
+    pub fn new(elf: &'static [u8], image_id: impl Into<ImageId>) -> Self {
+        Self {
+            elf,
+            image_id: image_id.into(),
+            compress: true,
+        }
+    }
+
+    /// Sets whether to compress receipts, or to produce composite receipts
+    /// whose assumptions grow with the persona's history.
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    pub fn image_id(&self) -> &ImageId {
+        &self.image_id
+    }
+
+    /// Proves an `Init` operation, creating a new [`Persona`].
+    ///
+    /// The assumptions are the receipts of any persona members signing the
+    /// operation, i.e. those whose journals are its [`PersonaSignature`]s.
+    ///
+    /// [`PersonaSignature`]: datalove_persona_core::PersonaSignature
+    pub fn init(
+        &self,
+        op: SignedOperation,
+        assumptions: impl IntoIterator<Item = Receipt>,
+    ) -> Result<Persona, Error> {
+        let sm = PersonaStateMachine::new(self.image_id);
+        let transition = sm.new_transition(op);
+        self.prove_transition(transition, None, assumptions)
+    }
+
+    /// Proves an operation against the persona's latest state, producing the
+    /// next [`Persona`] whose receipt assumes the previous one, along with the
+    /// receipts of any persona members signing the operation (as in
+    /// [`init`](Self::init)).
+    pub fn prove(
+        &self,
+        persona: Persona,
+        op: SignedOperation,
+        assumptions: impl IntoIterator<Item = Receipt>,
+    ) -> Result<Persona, Error> {
+        let (sm, receipt) = persona.into_parts();
+        let transition = sm.new_transition(op);
+        self.prove_transition(transition, Some((sm, receipt)), assumptions)
+    }
+
+    /// Wraps the persona's succinct receipt in a Groth16 receipt, e.g. for
+    /// verification on-chain.
+    ///
+    /// The persona retains its succinct receipt, as only succinct receipts
+    /// can be assumed by subsequent transitions. Requires an x86 host with
+    /// Docker.
+    pub fn groth16(&self, persona: &Persona) -> Result<Receipt, Error> {
+        let receipt = persona.receipt();
+        let InnerReceipt::Succinct(succinct) = &receipt.inner else {
+            return Err(Error::InvalidOperation(
+                "only succinct receipts can be wrapped in groth16",
+            ));
+        };
+
+        let seal = get_prover_server(&ProverOpts::default())
+            .and_then(|prover| prover.identity_p254(succinct))
+            .and_then(|p254| stark_to_snark(&p254.get_seal_bytes()))
+            .map_err(|err| Error::risc0_prover("failed to wrap receipt in groth16", err))?;
+        let claim = receipt
+            .get_claim()
+            .map_err(|err| Error::risc0_prover("failed to compute receipt claim", err))?;
+
+        let inner = InnerReceipt::Groth16(Groth16Receipt {
+            seal: seal.to_vec(),
+            claim,
+        });
+        Ok(Receipt::new(inner, receipt.journal.bytes.clone()))
+    }
+
+    /// Writes the transition and any previous state machine to the guest's
+    /// stdin, then reloads the next state machine from the guest's stdout
+    /// (its prover state) and journal (its verifier state).
+    fn prove_transition(
+        &self,
+        transition: Transition<SignedOperation>,
+        prev: Option<(PersonaStateMachine, Receipt)>,
+        assumptions: impl IntoIterator<Item = Receipt>,
+    ) -> Result<Persona, Error> {
+        let mut stdout = Vec::new();
+        let receipt = {
+            let mut env_builder = ExecutorEnv::builder();
+            for receipt in assumptions {
+                env_builder.add_assumption(receipt.into());
+            }
+            let sm = prev.map(|(sm, receipt)| {
+                env_builder.add_assumption(receipt.into());
+                sm
+            });
+
+            let env = env_builder
+                .stdout(&mut stdout)
+                .env_var("SELF_IMAGE_ID", &hex::encode(self.image_id.to_bytes()))
+                .write_slice(&borsh::to_vec(&transition)?)
+                .write_slice(&borsh::to_vec(&sm)?)
+                .build()
+                .map_err(|err| Error::risc0_prover("failed to build executor environment", err))?;
+
+            default_prover()
+                .prove(env, self.elf)
+                .map_err(|err| Error::risc0_prover("failed to prove transition", err))?
+        };
+        let receipt = if self.compress {
+            get_prover_server(&ProverOpts::default())
+                .and_then(|prover| prover.compress(&receipt))
+                .map_err(|err| Error::risc0_prover("failed to compress receipt", err))?
+        } else {
+            receipt
+        };
+
+        let sm = PersonaStateMachine::load(io::Read::chain(
+            stdout.as_slice(),
+            receipt.journal.bytes.as_slice(),
+        ))?;
+        Persona::new(sm, receipt, self.image_id)
+    }
+}
+
+impl Default for PersonaProver {
+    fn default() -> Self {
+        Self::new(V1_ELF, V1_ID)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datalove_persona_core::{
+        borsh::BorshDeserialize,
+        util::{risc0::TypedJournal, Sha256Digest},
+        Bump, Device, DeviceLog, GroupSignature, Init, Member, MemberSet, MemberSignature,
+        Operation, Persona as PersonaState, PersonaSignature, Sign,
+    };
+    use ed25519_dalek::SigningKey;
+
+    /// Signs an operation as a persona's device member.
+    fn device_sign(
+        sk: &SigningKey,
+        members: &MemberSet,
+        op: Operation,
+        state: &PersonaState,
+        log: &mut DeviceLog,
+    ) -> Result<SignedOperation, Error> {
+        let id = Device::from(sk.verifying_key()).id();
+        let op_digest = SignedOperation::op_digest(&op, state);
+        let member_sig = members.get(&id).unwrap().sign(&op_digest, sk, log)?;
+        let sig = members.add_signature(&id, member_sig, GroupSignature::default())?;
+        Ok(SignedOperation::new(op, sig))
+    }
+
+    #[test]
+    fn can_prove_persona() -> Result<(), Error> {
+        let sk = SigningKey::from_bytes(&[1; 32]);
+        let (device, mut log) = Device::init(sk.verifying_key(), &sk)?;
+        let members = MemberSet::new([Member::device(1, device.clone())])?;
+        let member_id = device.id();
+        let prover = PersonaProver::default();
+
+        let sign =
+            |members: &MemberSet, op: Operation, state: &PersonaState, log: &mut DeviceLog| {
+                let op_digest = SignedOperation::op_digest(&op, state);
+                let member_sig = members
+                    .get(&member_id)
+                    .unwrap()
+                    .sign(&op_digest, &sk, log)?;
+                let sig = members.add_signature(&member_id, member_sig, Default::default())?;
+                Ok::<_, Error>(SignedOperation::new(op, sig))
+            };
+
+        let metadata = Sha256Digest::from([1u32; 8]);
+        let op = Operation::Init(Init::new(metadata, &members));
+        let op = sign(&members, op, &PersonaState::DEFAULT, &mut log)?;
+        let members = members.advance(op.as_op(), op.signature())?;
+        let persona = prover.init(op, [])?;
+        let state = persona.state().verifier_state_ref();
+        assert_eq!(persona.did(), state.did());
+        assert_eq!(state.seqno(), 1);
+
+        let op = Operation::Bump(Bump::new(Some(metadata)));
+        let op = sign(&members, op, state, &mut log)?;
+        let next = prover.prove(persona, op, [])?;
+        assert_eq!(next.state().verifier_state_ref().seqno(), 2);
+        assert!(matches!(next.receipt().inner, InnerReceipt::Succinct(_)));
+
+        // personas roundtrip through their borsh encoding
+        let decoded = Persona::try_from_slice(&borsh::to_vec(&next)?)?;
+        assert_eq!(decoded.did(), next.did());
+        assert_eq!(
+            decoded.state().verifier_digest(),
+            next.state().verifier_digest()
+        );
+
+        // decoded personas are only trusted once verified against an image id
+        decoded.verify(*prover.image_id())?;
+        assert!(matches!(
+            decoded.verify(Sha256Digest::ZERO),
+            Err(Error::ImageIdMismatch { .. })
+        ));
+        let (state, receipt) = decoded.into_parts();
+        assert!(Persona::new(state, receipt, Sha256Digest::ZERO).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn can_prove_nested_persona() -> Result<(), Error> {
+        let prover = PersonaProver::default();
+
+        // a child persona, managed by a single device
+        let child_sk = SigningKey::from_bytes(&[2; 32]);
+        let (child_device, mut child_log) = Device::init(child_sk.verifying_key(), &child_sk)?;
+        let child_members = MemberSet::new([Member::device(1, child_device)])?;
+        let op = Operation::Init(Init::new(Sha256Digest::ZERO, &child_members));
+        let op = device_sign(
+            &child_sk,
+            &child_members,
+            op,
+            &PersonaState::DEFAULT,
+            &mut child_log,
+        )?;
+        let mut child_members = child_members.advance(op.as_op(), op.signature())?;
+        let mut child = prover.init(op, [])?;
+
+        // a parent persona, managed by a device and the child persona
+        let sk = SigningKey::from_bytes(&[1; 32]);
+        let (device, mut log) = Device::init(sk.verifying_key(), &sk)?;
+        let device_member = Member::device(1, device);
+        let persona_member = Member::persona(1, *child.did());
+        let members = MemberSet::new([device_member.clone(), persona_member.clone()])?;
+
+        // the child signs for the parent by proving a `Sign` of its member op
+        // digest, whose receipt the parent's proof must assume
+        let mut persona_sign = |op_digest: &Sha256Digest| -> Result<_, Error> {
+            let member_op_digest = *op_digest ^ persona_member.weight() as u32;
+            let op = Operation::Sign(Sign::new(Sha256Digest::ZERO, member_op_digest.as_ref()));
+            let state = child.state().verifier_state_ref().clone();
+            let op = device_sign(&child_sk, &child_members, op, &state, &mut child_log)?;
+            child_members = child_members.advance(op.as_op(), op.signature())?;
+            child = prover.prove(child.clone(), op, [])?;
+
+            let journal = TypedJournal::<(Sha256Digest, PersonaState)>::try_from_slice(
+                &child.receipt().journal.bytes,
+            )?;
+            let sig =
+                MemberSignature::persona(persona_member.weight(), PersonaSignature::from(journal));
+            Ok((sig, child.receipt().clone()))
+        };
+
+        // both members must sign the parent's init
+        let op = Operation::Init(Init::new(Sha256Digest::ZERO, &members));
+        let op_digest = SignedOperation::op_digest(&op, &PersonaState::DEFAULT);
+        let member_sig = device_member.sign(&op_digest, &sk, &mut log)?;
+        let sig = members.add_signature(&device_member.id(), member_sig, Default::default())?;
+        let (member_sig, receipt) = persona_sign(&op_digest)?;
+        let sig = members.add_signature(&persona_member.id(), member_sig, sig)?;
+        let parent = prover.init(SignedOperation::new(op, sig), [receipt])?;
+        assert_eq!(parent.state().verifier_state_ref().seqno(), 1);
+
+        // the child alone can sign a message as the parent
+        let op = Operation::Sign(Sign::new(Sha256Digest::ZERO, b"hello world"));
+        let op_digest = SignedOperation::op_digest(&op, parent.state().verifier_state_ref());
+        let (member_sig, receipt) = persona_sign(&op_digest)?;
+        let sig = members.add_signature(&persona_member.id(), member_sig, Default::default())?;
+        let op = SignedOperation::new(op, sig);
+
+        // which cannot be proven without the child's receipt
+        assert!(prover.prove(parent.clone(), op.clone(), []).is_err());
+        let parent = prover.prove(parent, op, [receipt])?;
+        let state = parent.state().verifier_state_ref();
+        assert_eq!(state.seqno(), 2);
+        assert_eq!(state.msg(), &Sign::message_digest(b"hello world"));
+        Ok(())
+    }
+
+    #[test]
+    #[ignore = "requires docker"]
+    fn can_wrap_persona_in_groth16() -> Result<(), Error> {
+        let sk = SigningKey::from_bytes(&[1; 32]);
+        let (device, mut log) = Device::init(sk.verifying_key(), &sk)?;
+        let members = MemberSet::new([Member::device(1, device.clone())])?;
+        let op = Operation::Init(Init::new(Sha256Digest::ZERO, &members));
+        let op_digest = SignedOperation::op_digest(&op, &PersonaState::DEFAULT);
+        let member_sig = members
+            .get(&device.id())
+            .unwrap()
+            .sign(&op_digest, &sk, &mut log)?;
+        let sig = members.add_signature(&device.id(), member_sig, Default::default())?;
+
+        let prover = PersonaProver::default();
+        let persona = prover.init(SignedOperation::new(op, sig), [])?;
+        let receipt = prover.groth16(&persona)?;
+        assert!(matches!(receipt.inner, InnerReceipt::Groth16(_)));
+        receipt
+            .verify(prover.image_id().to_bytes())
+            .map_err(|_| Error::Risc0VerificationError("receipt failed verification"))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+//! [`Store`] implementations backed by memory and the filesystem.
+
+use datalove_persona_core::{store::Store, util::Sha256Digest, Error};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// An in-memory [`Store`].
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStore {
+    values: HashMap<(String, [u8; 32]), Vec<u8>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+}
+
+impl Store for MemoryStore {
+    fn get_bytes(&self, namespace: &str, key: &Sha256Digest) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self
+            .values
+            .get(&(namespace.into(), key.to_bytes()))
+            .cloned())
+    }
+
+    fn put_bytes(
+        &mut self,
+        namespace: &str,
+        key: &Sha256Digest,
+        bytes: Vec<u8>,
+    ) -> Result<(), Error> {
+        self.values
+            .insert((namespace.into(), key.to_bytes()), bytes);
+        Ok(())
+    }
+
+    fn remove_bytes(&mut self, namespace: &str, key: &Sha256Digest) -> Result<(), Error> {
+        self.values.remove(&(namespace.into(), key.to_bytes()));
+        Ok(())
+    }
+}
+
+/// A [`Store`] that writes each value to its own file, at
+/// `<root>/<namespace>/<hex-encoded key>`.
+#[derive(Clone, Debug)]
+pub struct FileStore {
+    root: PathBuf,
+}
+
+impl FileStore {
+    /// Opens a store rooted at the given directory, creating it if needed.
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self, Error> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path(&self, namespace: &str, key: &Sha256Digest) -> PathBuf {
+        self.root.join(namespace).join(hex::encode(key.to_bytes()))
+    }
+}
+
+impl Store for FileStore {
+    fn get_bytes(&self, namespace: &str, key: &Sha256Digest) -> Result<Option<Vec<u8>>, Error> {
+        match fs::read(self.path(namespace, key)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Writes to a temporary file before renaming it into place, so that
+    /// readers never observe a partially-written value.
+    ///
+    /// Each write has its own temporary file, so concurrent writers (within
+    /// or across processes) never clobber each other's writes in progress.
+    fn put_bytes(
+        &mut self,
+        namespace: &str,
+        key: &Sha256Digest,
+        bytes: Vec<u8>,
+    ) -> Result<(), Error> {
+        static NEXT_TMP: AtomicU64 = AtomicU64::new(0);
+
+        let path = self.path(namespace, key);
+        let dir = self.root.join(namespace);
+        fs::create_dir_all(&dir)?;
+
+        let tmp = path.with_extension(format!(
+            "{}.{}.tmp",
+            process::id(),
+            NEXT_TMP.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    fn remove_bytes(&mut self, namespace: &str, key: &Sha256Digest) -> Result<(), Error> {
+        match fs::remove_file(self.path(namespace, key)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datalove_persona_core::{
+        zksm::StateMachine, Device, DeviceLog, DeviceLogNode, Group, Persona,
+    };
+    use ed25519_dalek::SigningKey;
+
+    type PersonaStateMachine = StateMachine<Group, Persona>;
+
+    fn device_log() -> Result<(Device, DeviceLog), Error> {
+        let sk = SigningKey::from_bytes(&[1u8; 32]);
+        let (mut device, mut log) = Device::init(sk.verifying_key(), &sk)?;
+        device.append(&b"hello world".to_vec(), &sk, &mut log)?;
+        Ok((device, log))
+    }
+
+    fn roundtrip(store: &mut impl Store) -> Result<(), Error> {
+        let (device, log) = device_log()?;
+        for node in log.iter() {
+            let key = node.leaf_hash();
+            assert!(!store.contains::<DeviceLogNode>(&key)?);
+            store.put(&key, node)?;
+            assert_eq!(store.get::<DeviceLogNode>(&key)?.as_ref(), Some(node));
+        }
+
+        let key = Sha256Digest::from(device.id());
+        store.put(&key, &log)?;
+        assert_eq!(store.get::<DeviceLog>(&key)?, Some(log));
+
+        let sm = PersonaStateMachine::default();
+        let key = sm.verifier_digest();
+        store.put(&key, &sm)?;
+        let stored = store.get::<PersonaStateMachine>(&key)?.unwrap();
+        assert_eq!(stored.verifier_digest(), key);
+
+        // values are namespaced by type
+        assert!(store.get::<DeviceLog>(&key)?.is_none());
+
+        store.remove::<PersonaStateMachine>(&key)?;
+        assert!(!store.contains::<PersonaStateMachine>(&key)?);
+        Ok(())
+    }
+
+    #[test]
+    fn can_use_memory_store() -> Result<(), Error> {
+        roundtrip(&mut MemoryStore::new())
+    }
+
+    #[test]
+    fn can_append_to_stored_device_log() -> Result<(), Error> {
+        let sk = SigningKey::from_bytes(&[1u8; 32]);
+        let mut store = MemoryStore::new();
+        let (mut device, log) = Device::init(sk.verifying_key(), &sk)?;
+        assert!(DeviceLog::load(&store, &device)?.is_empty());
+
+        log.save(&mut store, &device)?;
+        let stale = device.clone();
+        device.append_stored(&b"hello world".to_vec(), &sk, &mut store)?;
+
+        // the stored log and its nodes match an in-memory log
+        let (expected_device, expected_log) = device_log()?;
+        let log = DeviceLog::load(&store, &device)?;
+        assert_eq!(log, expected_log);
+        assert_eq!(device, expected_device);
+        assert_eq!(log.head(), *device.head());
+        for node in log.iter() {
+            assert_eq!(
+                store.get::<DeviceLogNode>(&node.leaf_hash())?.as_ref(),
+                Some(node)
+            );
+        }
+
+        // a stale device cannot append to the stored log
+        let mut stale = stale;
+        assert!(stale
+            .append_stored(&b"hello again".to_vec(), &sk, &mut store)
+            .is_err());
+        assert_eq!(DeviceLog::load(&store, &device)?, log);
+        Ok(())
+    }
+
+    #[test]
+    fn can_use_file_store() -> Result<(), Error> {
+        let root = std::env::temp_dir().join(format!("datalove-store-{}", std::process::id()));
+        let mut store = FileStore::open(&root)?;
+        roundtrip(&mut store)?;
+
+        // values persist across instances
+        let (_, log) = device_log()?;
+        let node = log.get(0).unwrap();
+        store.put(&node.leaf_hash(), node)?;
+        let reopened = FileStore::open(&root)?;
+        assert_eq!(
+            reopened.get::<DeviceLogNode>(&node.leaf_hash())?.as_ref(),
+            Some(node)
+        );
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "prove")]
+    #[test]
+    fn can_store_receipts() -> Result<(), Error> {
+        use crate::PersonaProver;
+        use datalove_persona_core::{
+            GroupSignature, Init, Member, MemberSet, Operation, SignedOperation,
+        };
+        use risc0_zkvm::Receipt;
+
+        let sk = SigningKey::from_bytes(&[1u8; 32]);
+        let member = Member::device(1, Device::from(sk.verifying_key()));
+        let members = MemberSet::new([member.clone()])?;
+        let op = Operation::Init(Init::new(Sha256Digest::ZERO, &members));
+        let op_digest = SignedOperation::op_digest(&op, &Persona::DEFAULT);
+        let member_sig = member.sign(&op_digest, &sk, &mut DeviceLog::default())?;
+        let sig = members.add_signature(&member.id(), member_sig, GroupSignature::default())?;
+        let persona = PersonaProver::default().init(SignedOperation::new(op, sig), [])?;
+
+        let mut store = MemoryStore::new();
+        let key = persona.state().verifier_digest();
+        store.put(&key, persona.receipt())?;
+        let stored = store.get::<Receipt>(&key)?.unwrap();
+        assert_eq!(stored.journal.bytes, persona.receipt().journal.bytes);
+        assert!(store.get::<PersonaStateMachine>(&key)?.is_none());
+        Ok(())
+    }
+}
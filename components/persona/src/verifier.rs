@@ -0,0 +1,500 @@
+//! Verifies [`Receipt`]s of persona transitions, without a prover.
+
+use crate::PersonaStateMachine;
+use datalove_persona_core::{
+    borsh::BorshDeserialize,
+    util::{risc0::TypedJournal, ImageId, Sha256Digest},
+    zksm::{Transition, VState},
+    Did, Error, Operation, Persona as PersonaState, SignedOperation,
+};
+use datalove_persona_risc0::DATALOVE_PERSONA_RISC0_GUEST_V1_ID as V1_ID;
+use risc0_zkvm::Receipt;
+
+/// A persona's public state, as committed to by a verified journal.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerifiedPersona {
+    digest: Sha256Digest,
+    state: PersonaState,
+}
+
+impl VerifiedPersona {
+    /// Digest of the journal, i.e. the verifier digest of the persona's state
+    /// machine.
+    pub fn digest(&self) -> &Sha256Digest {
+        &self.digest
+    }
+
+    pub fn did(&self) -> &Did {
+        self.state.did()
+    }
+
+    pub fn seqno(&self) -> u32 {
+        self.state.seqno()
+    }
+
+    pub fn metadata(&self) -> &Sha256Digest {
+        self.state.metadata()
+    }
+
+    pub fn msg(&self) -> &Sha256Digest {
+        self.state.msg()
+    }
+
+    /// Digest of the transition the journal commits to.
+    pub fn op(&self) -> &Sha256Digest {
+        self.state.op()
+    }
+
+    /// Digest of the journal this journal extends.
+    pub fn prev(&self) -> &Sha256Digest {
+        self.state.prev()
+    }
+
+    pub fn state(&self) -> &PersonaState {
+        &self.state
+    }
+}
+
+/// Verifies persona receipts produced by a persona guest, by default the v1
+/// guest.
+#[derive(Clone, Debug)]
+pub struct PersonaVerifier {
+    image_id: ImageId,
+}
+
+impl PersonaVerifier {
+    pub fn new(image_id: impl Into<ImageId>) -> Self {
+        Self {
+            image_id: image_id.into(),
+        }
+    }
+
+    pub fn image_id(&self) -> &ImageId {
+        &self.image_id
+    }
+
+    /// Verifies the receipt's seal against the guest's image id, then decodes
+    /// its journal.
+    pub fn verify(&self, receipt: &Receipt) -> Result<VerifiedPersona, Error> {
+        receipt
+            .verify(self.image_id.to_bytes())
+            .map_err(|_| Error::Risc0VerificationError("receipt failed verification"))?;
+        self.verify_journal(&receipt.journal.bytes)
+    }
+
+    /// Decodes a journal of the guest, asserting it was committed by the
+    /// guest to an initialized persona.
+    ///
+    /// The journal's seal is not verified, so the journal must be otherwise
+    /// known to belong to a valid receipt.
+    pub fn verify_journal(&self, journal: &[u8]) -> Result<VerifiedPersona, Error> {
+        let journal = TypedJournal::<VState<PersonaState>>::try_from_slice(journal)
+            .map_err(|_| Error::InvalidJournal("failed to decode persona journal"))?;
+        if journal.image_id() != &self.image_id {
+            return Err(Error::ImageIdMismatch {
+                expected: self.image_id,
+                found: *journal.image_id(),
+            });
+        }
+
+        let digest = *journal.digest();
+        let state = journal.into_inner().into_state();
+        if state.did() == &Did::ZERO || state.seqno() == 0 {
+            return Err(Error::InvalidJournal("persona is not initialized"));
+        }
+        Ok(VerifiedPersona { digest, state })
+    }
+
+    /// Verifies a persona's history from its `Init` onward from its journals
+    /// alone, returning its latest state.
+    ///
+    /// Each journal must extend the previous journal (or, for the `Init`, the
+    /// default state machine), preserve the persona's DID and increment its
+    /// seqno. If provided, the receipt must be valid and commit to the last
+    /// journal, and thus (by assuming each previous receipt) to the entire
+    /// history.
+    ///
+    /// Fails with [`Error::InvalidHistory`], identifying the first invalid
+    /// step.
+    pub fn verify_journals<'a, I>(
+        &self,
+        journals: I,
+        receipt: Option<&Receipt>,
+    ) -> Result<VerifiedPersona, Error>
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        self.verify_steps(journals.into_iter().map(|journal| (journal, None)), receipt)
+    }
+
+    /// Verifies a persona's history as in [`Self::verify_journals`], and that
+    /// each journal commits to the given transition.
+    ///
+    /// Each step is a journal and the (private) transition it proves, in
+    /// order, and each transition must commit to the previous journal.
+    pub fn verify_history<'a, I>(
+        &self,
+        steps: I,
+        receipt: Option<&Receipt>,
+    ) -> Result<VerifiedPersona, Error>
+    where
+        I: IntoIterator<Item = (&'a [u8], &'a Transition<SignedOperation>)>,
+    {
+        let steps = steps
+            .into_iter()
+            .map(|(journal, transition)| (journal, Some(transition)));
+        self.verify_steps(steps, receipt)
+    }
+
+    fn verify_steps<'a, I>(
+        &self,
+        steps: I,
+        receipt: Option<&Receipt>,
+    ) -> Result<VerifiedPersona, Error>
+    where
+        I: Iterator<Item = (&'a [u8], Option<&'a Transition<SignedOperation>>)>,
+    {
+        let invalid = |step, err| Error::InvalidHistory {
+            step,
+            source: Box::new(err),
+        };
+
+        let mut prev_digest = PersonaStateMachine::new(self.image_id).verifier_digest();
+        let mut prev: Option<VerifiedPersona> = None;
+        let mut len = 0;
+        for (step, (journal, transition)) in steps.enumerate() {
+            let verified = self
+                .verify_step(prev.as_ref(), &prev_digest, journal, transition)
+                .map_err(|err| invalid(step, err))?;
+            prev_digest = verified.digest;
+            prev = Some(verified);
+            len = step + 1;
+        }
+
+        let latest = prev.ok_or_else(|| invalid(0, Error::InvalidJournal("history is empty")))?;
+        if let Some(receipt) = receipt {
+            let verified = self.verify(receipt).map_err(|err| invalid(len - 1, err))?;
+            if verified.digest != latest.digest {
+                return Err(invalid(
+                    len - 1,
+                    Error::InvalidJournal("receipt does not commit to latest journal"),
+                ));
+            }
+        }
+        Ok(latest)
+    }
+
+    fn verify_step(
+        &self,
+        prev: Option<&VerifiedPersona>,
+        prev_digest: &Sha256Digest,
+        journal: &[u8],
+        transition: Option<&Transition<SignedOperation>>,
+    ) -> Result<VerifiedPersona, Error> {
+        let verified = self.verify_journal(journal)?;
+        if verified.prev() != prev_digest {
+            return Err(Error::InvalidJournal(
+                "journal does not extend previous journal",
+            ));
+        }
+
+        if let Some(transition) = transition {
+            let (op_digest, signed_op) = transition.as_op();
+            if transition.op_commitment() != prev_digest {
+                return Err(Error::InvalidOperation(
+                    "transition does not commit to previous journal",
+                ));
+            }
+            if verified.op() != &*op_digest {
+                return Err(Error::InvalidJournal(
+                    "journal does not commit to transition",
+                ));
+            }
+
+            let is_init = matches!(signed_op.as_op(), Operation::Init(_));
+            match prev {
+                None if !is_init => {
+                    return Err(Error::InvalidOperation("history must begin with Init"))
+                }
+                Some(_) if is_init => {
+                    return Err(Error::InvalidOperation("Init must begin history"))
+                }
+                _ => {}
+            }
+        }
+
+        match prev {
+            Some(prev) if verified.did() != prev.did() => {
+                Err(Error::InvalidJournal("persona DID changed"))
+            }
+            _ if verified.seqno() != prev.map_or(0, VerifiedPersona::seqno) + 1 => {
+                Err(Error::InvalidJournal("seqno is not contiguous"))
+            }
+            _ => Ok(verified),
+        }
+    }
+}
+
+impl Default for PersonaVerifier {
+    fn default() -> Self {
+        Self::new(V1_ID)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "prove")]
+    use crate::PersonaProver;
+    use datalove_persona_core::{Bump, Device, DeviceLog, GroupSignature, Init, Member, MemberSet};
+    use ed25519_dalek::SigningKey;
+
+    fn sign(
+        sk: &SigningKey,
+        members: &MemberSet,
+        op: Operation,
+        state: &PersonaState,
+        log: &mut DeviceLog,
+    ) -> Result<SignedOperation, Error> {
+        let id = Device::from(sk.verifying_key()).id();
+        let op_digest = SignedOperation::op_digest(&op, state);
+        let member_sig = members.get(&id).unwrap().sign(&op_digest, sk, log)?;
+        let sig = members.add_signature(&id, member_sig, GroupSignature::default())?;
+        Ok(SignedOperation::new(op, sig))
+    }
+
+    /// Encodes the journal the guest commits for the state machine.
+    fn journal(sm: &PersonaStateMachine, image_id: ImageId) -> Vec<u8> {
+        let state = (*sm.verifier_commitment(), sm.verifier_state_ref().clone());
+        datalove_persona_core::borsh::to_vec(&TypedJournal::new(image_id, state)).unwrap()
+    }
+
+    /// Runs an `Init` and a `Bump` of a persona without proving them,
+    /// returning each step's journal and transition, and the persona's
+    /// members.
+    fn fixture_history(
+        verifier: &PersonaVerifier,
+    ) -> Result<(Vec<(Vec<u8>, Transition<SignedOperation>)>, MemberSet), Error> {
+        let sk = SigningKey::from_bytes(&[1; 32]);
+        let mut members = MemberSet::new([Member::device(1, Device::from(sk.verifying_key()))])?;
+        let mut log = DeviceLog::default();
+        let mut sm = PersonaStateMachine::new(*verifier.image_id());
+        let mut steps = Vec::new();
+
+        let ops = [
+            Operation::Init(Init::new(Sha256Digest::ZERO, &members)),
+            Operation::Bump(Bump::new(None)),
+        ];
+        for op in ops {
+            let op = sign(&sk, &members, op, sm.verifier_state_ref(), &mut log)?;
+            members = members.advance(op.as_op(), op.signature())?;
+            let transition = sm.new_transition(op.clone());
+            let next = sm.new_transition(op);
+            sm = sm.run(next)?;
+            steps.push((journal(&sm, *verifier.image_id()), transition));
+        }
+        Ok((steps, members))
+    }
+
+    #[test]
+    fn can_verify_journals() -> Result<(), Error> {
+        let verifier = PersonaVerifier::default();
+        let (steps, _) = fixture_history(&verifier)?;
+        let [(init_journal, init), (bump_journal, bump)] = &steps[..] else {
+            unreachable!()
+        };
+
+        let latest = verifier.verify_journals([&init_journal[..], &bump_journal[..]], None)?;
+        assert_eq!(latest.seqno(), 2);
+        assert_eq!(
+            latest.prev(),
+            verifier.verify_journal(init_journal)?.digest()
+        );
+        let history = [(&init_journal[..], init), (&bump_journal[..], bump)];
+        assert_eq!(verifier.verify_history(history, None)?, latest);
+
+        // journals must extend one another from the init
+        assert!(matches!(
+            verifier.verify_journals([&bump_journal[..]], None),
+            Err(Error::InvalidHistory { step: 0, .. })
+        ));
+        assert!(matches!(
+            verifier.verify_journals([&init_journal[..], &init_journal[..]], None),
+            Err(Error::InvalidHistory { step: 1, .. })
+        ));
+
+        // and, when provided, commit to their transitions
+        let unlinked = [(&init_journal[..], init), (&bump_journal[..], init)];
+        assert!(matches!(
+            verifier.verify_history(unlinked, None),
+            Err(Error::InvalidHistory { step: 1, .. })
+        ));
+
+        // journals must be from the expected guest
+        assert!(matches!(
+            PersonaVerifier::new(Sha256Digest::from([1u32; 8])).verify_journal(init_journal),
+            Err(Error::ImageIdMismatch { .. })
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "did")]
+    #[test]
+    fn can_resolve_verified_journals() -> Result<(), Error> {
+        use datalove_persona_core::PersonaDid;
+
+        let verifier = PersonaVerifier::default();
+        let (steps, members) = fixture_history(&verifier)?;
+        let journals = steps.iter().map(|(journal, _)| &journal[..]);
+        let latest = verifier.verify_journals(journals, None)?;
+
+        let sk = SigningKey::from_bytes(&[1; 32]);
+        let id = Device::from(sk.verifying_key()).id();
+        let member = members.get(&id).unwrap().clone();
+        let doc = latest.did_document(&[(member, members.proof(&id))], None)?;
+        assert_eq!(doc.id(), PersonaDid::new(*latest.did()).to_string());
+        assert_eq!(doc.verification_method().len(), 1);
+        Ok(())
+    }
+
+    #[cfg(feature = "prove")]
+    #[test]
+    fn can_verify_persona() -> Result<(), Error> {
+        let sk = SigningKey::from_bytes(&[1; 32]);
+        let members = MemberSet::new([Member::device(1, Device::from(sk.verifying_key()))])?;
+        let metadata = Sha256Digest::from([1u32; 8]);
+        let op = Operation::Init(Init::new(metadata, &members));
+        let op = sign(
+            &sk,
+            &members,
+            op,
+            &PersonaState::DEFAULT,
+            &mut DeviceLog::default(),
+        )?;
+        let persona = PersonaProver::default().init(op, [])?;
+
+        let verified = PersonaVerifier::default().verify(persona.receipt())?;
+        assert_eq!(verified.did(), persona.did());
+        assert_eq!(verified.seqno(), 1);
+        assert_eq!(verified.metadata(), &metadata);
+        assert_eq!(verified.state(), persona.state().verifier_state_ref());
+        assert_eq!(verified.digest(), &persona.state().verifier_digest());
+
+        // receipts must be from the expected guest
+        let other = PersonaVerifier::new(Sha256Digest::from([1u32; 8]));
+        assert!(other.verify(persona.receipt()).is_err());
+        assert!(matches!(
+            other.verify_journal(&persona.receipt().journal.bytes),
+            Err(Error::ImageIdMismatch { .. })
+        ));
+        assert!(matches!(
+            PersonaVerifier::default().verify_journal(&[0; 4]),
+            Err(Error::InvalidJournal(_))
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "prove")]
+    #[test]
+    fn can_verify_history() -> Result<(), Error> {
+        let sk = SigningKey::from_bytes(&[1; 32]);
+        let members = MemberSet::new([Member::device(1, Device::from(sk.verifying_key()))])?;
+        let mut log = DeviceLog::default();
+        let prover = PersonaProver::default();
+        let verifier = PersonaVerifier::default();
+
+        let op = Operation::Init(Init::new(Sha256Digest::ZERO, &members));
+        let op = sign(&sk, &members, op, &PersonaState::DEFAULT, &mut log)?;
+        let members = members.advance(op.as_op(), op.signature())?;
+        let init = PersonaStateMachine::new(*prover.image_id()).new_transition(op.clone());
+        let persona = prover.init(op, [])?;
+        let init_journal = persona.receipt().journal.bytes.clone();
+
+        let op = Operation::Bump(Bump::new(None));
+        let op = sign(
+            &sk,
+            &members,
+            op,
+            persona.state().verifier_state_ref(),
+            &mut log,
+        )?;
+        let bump = persona.state().new_transition(op.clone());
+
+        // a fork of the persona, bumped to other metadata
+        let fork_op = Operation::Bump(Bump::new(Some(Sha256Digest::from([1u32; 8]))));
+        let fork_op = sign(
+            &sk,
+            &members,
+            fork_op,
+            persona.state().verifier_state_ref(),
+            &mut log.clone(),
+        )?;
+        let fork = prover.prove(persona.clone(), fork_op, [])?;
+        let fork_journal = fork.receipt().journal.bytes.clone();
+
+        let persona = prover.prove(persona, op, [])?;
+        let bump_journal = persona.receipt().journal.bytes.clone();
+
+        let history = [(&init_journal[..], &init), (&bump_journal[..], &bump)];
+        let latest = verifier.verify_history(history, Some(persona.receipt()))?;
+        assert_eq!(latest.seqno(), 2);
+        assert_eq!(latest.did(), persona.did());
+
+        // journals alone chain to one another, without their transitions
+        let journals = [&init_journal[..], &bump_journal[..]];
+        assert_eq!(
+            verifier.verify_journals(journals, Some(persona.receipt()))?,
+            latest
+        );
+        assert_eq!(
+            latest.prev(),
+            verifier.verify_journal(&init_journal)?.digest()
+        );
+        let journals = [&bump_journal[..], &init_journal[..]];
+        assert!(matches!(
+            verifier.verify_journals(journals, None),
+            Err(Error::InvalidHistory { step: 0, .. })
+        ));
+        let journals = [&init_journal[..], &init_journal[..]];
+        assert!(matches!(
+            verifier.verify_journals(journals, None),
+            Err(Error::InvalidHistory { step: 1, .. })
+        ));
+
+        // a fork's journals form a history of their own, just not the one
+        // proven by the receipt
+        let journals = [&init_journal[..], &fork_journal[..]];
+        verifier.verify_journals(journals, None)?;
+        assert!(matches!(
+            verifier.verify_journals(journals, Some(persona.receipt())),
+            Err(Error::InvalidHistory { step: 1, .. })
+        ));
+
+        // steps must begin with the init, and each must extend the previous
+        let reordered = [(&bump_journal[..], &bump), (&init_journal[..], &init)];
+        assert!(matches!(
+            verifier.verify_history(reordered, None),
+            Err(Error::InvalidHistory { step: 0, .. })
+        ));
+        let unlinked = [(&init_journal[..], &init), (&bump_journal[..], &init)];
+        assert!(matches!(
+            verifier.verify_history(unlinked, None),
+            Err(Error::InvalidHistory { step: 1, .. })
+        ));
+
+        // each journal must be of its own transition, not that of a fork
+        let spliced = [(&init_journal[..], &init), (&fork_journal[..], &bump)];
+        assert!(matches!(
+            verifier.verify_history(spliced, None),
+            Err(Error::InvalidHistory { step: 1, .. })
+        ));
+
+        // the receipt must prove the latest step
+        let truncated = [(&init_journal[..], &init)];
+        assert!(matches!(
+            verifier.verify_history(truncated, Some(persona.receipt())),
+            Err(Error::InvalidHistory { step: 0, .. })
+        ));
+        Ok(())
+    }
+}